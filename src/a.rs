@@ -1,9 +1,16 @@
 use lib::{parallel::server::com_for_test, graph::{SeqSPartition, NearGraph}, io::example::MyEmpty, algo::pagerank};
 fn main() {
     let communicatoner = com_for_test(5, 6, 1);
-    let edges = vec![];
+    let graph = match NearGraph::<MyEmpty, SeqSPartition>::load_snapshot("data/1000w.snapshot".into(), &communicatoner) {
+        Some(graph) => graph,
+        None => {
+            let edges = vec![];
 
-    let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, &communicatoner);
+            let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, &communicatoner);
+            graph.save_snapshot("data/1000w.snapshot".into(), &communicatoner);
+            graph
+        }
+    };
 
     let pool = rayon::ThreadPoolBuilder::new().num_threads(3).build().unwrap();
     let pr = pool.install(||pagerank(graph, &communicatoner));
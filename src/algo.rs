@@ -1,39 +1,213 @@
+use std::time::Instant;
+use std::collections::{BTreeMap, HashMap};
 use bincode::{Encode, Decode};
-use std::{fmt::Debug, time::Instant};
-use rayon::iter::{IntoParallelRefMutIterator, IntoParallelRefIterator, ParallelIterator, IntoParallelIterator, IndexedParallelIterator};
+use rayon::iter::{ParallelIterator, IntoParallelIterator};
 
-use crate::{graph::{Graph, SeqPartition, NearGraph}, common::{base_structure::{Edge, Vid}, util::SharedPtr}, parallel::server::MyMpi};
+use crate::{graph::{Graph, Pratition, SeqPartition, NearGraph}, common::{util::SharedPtr, base_structure::{Vid, Edge}}, parallel::server::MyMpi, traits::Weight};
 
-pub fn pagerank<EDATA, PART>(graph : NearGraph<EDATA, PART>, communication : &impl MyMpi) -> Vec<f32> 
+use std::fmt::Debug;
+
+pub mod ppr;
+pub mod bfs;
+pub mod sssp;
+pub mod wcc;
+pub mod scc;
+pub mod triangle;
+pub mod kcore;
+pub mod lpa;
+pub mod louvain;
+
+/// 本模块的算法都要求本 rank 拥有的顶点的邻接表完整。vertex-cut 划分下一个顶点的边分散在多个 rank 上，
+/// 直接用本地的度数和邻居会得到错误的结果，所以在入口处拒绝
+pub(crate) fn assert_edge_cut(partition : &impl SeqPartition, algo : &str) {
+    assert!(!partition.vertex_cut(), "{algo} 要求本 rank 拥有的顶点的邻接表完整，不支持 vertex-cut 划分");
+}
+
+/// 将 msgs[i] 发送到 rank i，返回所有 rank 发给本 rank 的消息
+pub(crate) fn exchange<T>(msgs : Vec<Vec<T>>, communication : &impl MyMpi) -> Vec<T>
+where
+    T : Encode + Decode + Send + 'static,
+{
+    let recv = communication.send_recv::<Vec<T>>(msgs);
+    recv.into_iter().flatten().collect()
+}
+
+/// 向 ids 的 owner 请求 value(id)，返回 id -> value。所有 rank 需要同时调用
+pub(crate) fn fetch<G, T>(graph : &G, ids : Vec<Vid>, value : impl Fn(Vid) -> T, communication : &impl MyMpi) -> HashMap<Vid, T>
+where
+    G : Graph,
+    T : Encode + Decode + Send + 'static,
+{
+    let partition = graph.partition();
+    let mut requests = vec![vec![]; communication.partitions()];
+    for id in ids {
+        requests[partition.vertex_partition(&id)].push(id);
+    }
+    let recv = communication.send_recv::<Vec<Vid>>(requests);
+    let replies : Vec<Vec<(Vid, T)>> = recv.into_iter().map(|ids| ids.into_iter().map(|id| (id, value(id))).collect()).collect();
+    communication.send_recv::<Vec<(Vid, T)>>(replies).into_iter().flatten().collect()
+}
+
+/// 统计每个标签下的顶点个数并汇总到 rank 0，返回按标签升序的 (标签, 个数)，只在 rank 0 上返回 Some
+pub(crate) fn gather_sizes(label : &[Vid], communication : &impl MyMpi) -> Option<Vec<(Vid, Vid)>> {
+    let mut local : BTreeMap<Vid, Vid> = BTreeMap::new();
+    for &l in label {
+        *local.entry(l).or_default() += 1;
+    }
+    let recv = communication.gather(local.into_iter().collect::<Vec<(Vid, Vid)>>(), 0);
+    if communication.get_cluster_info().rank != 0 {
+        return None;
+    }
+    let mut merged : BTreeMap<Vid, Vid> = BTreeMap::new();
+    for (l, size) in recv.into_iter().flatten() {
+        *merged.entry(l).or_default() += size;
+    }
+    Some(merged.into_iter().collect())
+}
+
+/// pagerank 的参数
+#[derive(Debug, Clone)]
+pub struct PageRankConfig {
+    pub damping : f32,
+
+    pub max_iterations : usize,
+
+    /// 相邻两轮 pr 的全局 L1 距离小于它时提前停止，为 0 时总是跑满 max_iterations
+    pub tolerance : f64,
+
+    /// 为 true 时 pr 之和为 1：pr(v) = (1 - d) / N + d * Σ pr(u) / deg(u)；
+    /// 为 false 时沿用 pr(v) = 1 - d + d * Σ pr(u) / deg(u)，pr 之和为 N
+    pub normalized : bool,
+
+    /// 把度数为 0 的顶点上的 pr 按跳转分布重新分配（没有 seeds 时平均分给所有顶点），否则这部分 pr 不再传播
+    pub redistribute_dangling : bool,
+
+    /// personalized pagerank 的种子顶点，所有 rank 上相同。非空时只跳转回这些顶点，为空时跳转到所有顶点
+    pub seeds : Vec<Vid>,
+}
+
+impl PageRankConfig {
+    pub fn default() -> Self {
+        PageRankConfig {
+            damping : 0.85,
+            max_iterations : 100,
+            tolerance : 1e-6,
+            normalized : false,
+            redistribute_dangling : false,
+            seeds : vec![],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PageRankResult {
+    /// 本 rank 拥有的顶点的 pr
+    pub pr : Vec<f32>,
+
+    /// 实际迭代的轮数
+    pub iterations : usize,
+
+    /// 最后一轮的全局 L1 残差
+    pub residual : f64,
+}
+
+/// 本 rank 拥有的每个顶点的跳转概率：没有 seeds 时为 1 / N，否则只在 seeds 上为 1 / |seeds|
+fn jump_probability(config : &PageRankConfig, start_id : usize, end_id : usize, vertex_num : usize, communication : &impl MyMpi) -> Vec<f32> {
+    let mut is_seed = vec![false; end_id - start_id];
+    config.seeds.iter().filter(|&&seed| seed as usize >= start_id && (seed as usize) < end_id).for_each(|&seed| is_seed[seed as usize - start_id] = true);
+    let seed_num = communication.reduce(is_seed.iter().filter(|&&x| x).count(), |a, b| a + b);
+    is_seed.into_iter().map(|seed| {
+        if config.seeds.is_empty() {
+            1.0 / vertex_num as f32
+        }else if seed {
+            1.0 / seed_num as f32
+        }else {
+            0.0
+        }
+    }).collect()
+}
+
+/// 按默认参数计算 pagerank
+pub fn pagerank<G>(graph : G, communication : &impl MyMpi) -> Vec<f32> 
+where
+    G : Graph + Sync,
+{
+    pagerank_with_config(&graph, &PageRankConfig::default(), communication).pr
+}
+
+/// 按 config 计算 pagerank，要求本 rank 拥有的顶点的邻接表完整（不支持 vertex-cut 划分）：
+/// 出度直接取本地邻接表，贡献也只沿本地邻接表发送
+pub fn pagerank_with_config<G>(graph : &G, config : &PageRankConfig, communication : &impl MyMpi) -> PageRankResult 
+where
+    G : Graph + Sync,
+{
+    assert_edge_cut(graph.partition(), "pagerank_with_config");
+    // 不带权时每条边的权重为 1，边权之和就是度数
+    pagerank_loop(graph, config, |id, f| graph.for_each_nbr(id, |to| f(to, 1.0)), communication)
+}
+
+/// 带权 pagerank，u 按边权的比例把 pr 分给邻居：pr(v) = (1 - d) * jump(v) + d * Σ pr(u) * w(u, v) / W(u)，W(u) 为 u 的边权之和。
+/// 参数的含义同 pagerank_with_config，边权之和为 0 的顶点视为度数为 0
+pub fn weighted_pagerank<EDATA, PART>(graph : &NearGraph<EDATA, PART>, config : &PageRankConfig, communication : &impl MyMpi) -> PageRankResult
 where
     PART : SeqPartition + Sync,
-    EDATA : Clone + Send + Sync + Debug,
+    EDATA : Weight + Clone + Send + Sync + Debug,
     Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
 {
+    assert_edge_cut(graph.partition(), "weighted_pagerank");
+    pagerank_loop(graph, config, |id, f| graph.for_each_weighted_nbr(id, f), communication)
+}
 
-    let vertexs = graph.graph_info.vertex_num as usize;
+/// pagerank_with_config 和 weighted_pagerank 共用的迭代。for_each_nbr(id, f) 对 id 的每个邻居 to 调用 f(to, w(id, to))，
+/// u 按边权的比例把 pr 分给邻居
+fn pagerank_loop<G>(graph : &G, config : &PageRankConfig, for_each_nbr : impl Fn(usize, &mut dyn FnMut(Vid, f32)) + Sync, communication : &impl MyMpi) -> PageRankResult
+where
+    G : Graph + Sync,
+{
     let start_id = graph.partition().start_id() as usize;
     let end_id = graph.partition().end_id() as usize;
 
-    let local_degree = graph.degrees();
-    // println!("local_degree: {:?}", local_degree);
-    let mut local_pr : Vec<f32> = vec![1.0; local_degree.len()];
+    let local_weight : Vec<f32> = (start_id..end_id).into_par_iter().map(|id| {
+        let mut sum = 0.0;
+        for_each_nbr(id, &mut |_, w| sum += w);
+        sum
+    }).collect();
 
-    let global_degree : Vec<u32> = {
-        let msgs = vec![local_degree; communication.partitions()];
+    let global_weight : Vec<f32> = {
+        let msgs = vec![local_weight.clone(); communication.partitions()];
 
-        let recv = communication.send_recv::<Vec<u32>>(msgs);
+        let recv = communication.send_recv::<Vec<f32>>(msgs);
 
         recv.into_par_iter().flatten().collect()
     };
-    // println!("global_degree: {:?}", global_degree);
 
-    let iteration = 100;
-    let damping = 0.85;
+    let vertex_num = global_weight.len() as f32;
+    let damping = config.damping;
+    let (init, scale) = if config.normalized {
+        (1.0 / vertex_num, 1.0)
+    }else {
+        (1.0, vertex_num)
+    };
+    let mut local_pr : Vec<f32> = vec![init; local_weight.len()];
+
+    let jump = jump_probability(config, start_id, end_id, global_weight.len(), communication);
+    let jump = |id : usize| jump[id - start_id];
+
     let p = SharedPtr::new(local_pr.as_mut_ptr());
-    for i in 0..iteration {
+    let mut iterations = 0;
+    let mut residual = f64::MAX;
+    for i in 0..config.max_iterations {
         let t00 = Instant::now();
         println!("iter: {i}");
+
+        // 边权之和为 0 的顶点上的 pr 总和，按跳转概率分给各个顶点
+        let dangling = if config.redistribute_dangling {
+            let local_dangling : f64 = local_pr.iter().zip(local_weight.iter()).filter(|(_, &weight)| weight == 0.0).map(|(&pr, _)| pr as f64).sum();
+            communication.reduce(local_dangling, |a, b| a + b) as f32
+        }else {
+            0.0
+        };
+
         let mut t0 = Instant::now();
         let msgs = vec![local_pr.clone(); communication.partitions()];
         println!("prepare msgs cost: {:?}", Instant::now() - t0);
@@ -45,26 +219,160 @@ where
         t0 = Instant::now();
         let global_pr : Vec<f32> = recv.into_par_iter().flatten().collect();
         println!("get global_pr cost: {:?}", Instant::now() - t0);
-        // println!("get global_pr {:?}", global_pr);
 
         t0 = Instant::now();
-        (start_id..end_id).into_par_iter().for_each(|id|{
-            let nbr = graph.nbr(id);
+        let local_residual : f64 = (start_id..end_id).into_par_iter().map(|id|{
             let mut sum = 0.0;
-            nbr.iter().for_each(|edge| {
-                sum += global_pr[edge.to as usize] / global_degree[edge.to as usize] as f32;
+            for_each_nbr(id, &mut |to, w| {
+                let weight = global_weight[to as usize];
+                if weight > 0.0 {
+                    sum += global_pr[to as usize] * w / weight;
+                }
             });
-            // println!("id: {id} sum: {sum} bnr: {:?}", nbr);
+            let pr = (1.0 - damping) * scale * jump(id) + damping * (sum + dangling * jump(id));
+            let old = global_pr[id];
             unsafe {
-                *p.add(id - start_id) = 1.0 - damping + damping * sum;
+                *p.add(id - start_id) = pr;
             };
-        });
+            (pr - old).abs() as f64
+        }).sum();
         println!("calc local pr cost: {:?}", Instant::now() - t0);
-        // println!("calc local prr {:?}", local_pr);
 
-        println!("------------------------------iter {i} cost: {:?}", Instant::now() - t00);
+        iterations = i + 1;
+        residual = communication.reduce(local_residual, |a, b| a + b);
+        println!("------------------------------iter {i} residual: {residual} cost: {:?}", Instant::now() - t00);
+        if residual < config.tolerance {
+            break;
+        }
     }
 
+    PageRankResult {
+        pr : local_pr,
+        iterations : iterations,
+        residual : residual,
+    }
+}
+
+/// 图更新后的增量 pagerank（push-based delta PageRank）。
+/// 以更新前的结果 prev 为起点，只有受影响顶点及其邻居的残差可能不为 0，从这些顶点开始推送残差直到收敛。
+/// prev 是更新前按同一个 config 算出的本 rank 拥有顶点的 pr，affected 是 apply_updates 返回的本 rank 受影响顶点，
+/// 新增的顶点以 (1 - d) * jump(v) 为初值。残差绝对值不超过 tolerance / 顶点数的顶点不再推送，
+/// 这样停止时剩下的残差之和不超过 tolerance；最多推送 max_iterations 轮
+pub fn pagerank_incremental<G>(graph : &G, prev : Vec<f32>, affected : &[Vid], config : &PageRankConfig, communication : &impl MyMpi) -> Vec<f32>
+where
+    G : Graph + Sync,
+{
+    assert_edge_cut(graph.partition(), "pagerank_incremental");
+    let partitions = communication.partitions();
+    let rank = communication.get_cluster_info().rank;
+    let partition = graph.partition();
+    let start_id = partition.start_id() as usize;
+    let end_id = partition.end_id() as usize;
+    let damping = config.damping;
+
+    let vertex_num = communication.reduce(end_id - start_id, |a, b| a + b);
+    let scale = if config.normalized { 1.0 } else { vertex_num as f32 };
+    let jump = jump_probability(config, start_id, end_id, vertex_num, communication);
+    let threshold = (config.tolerance / vertex_num.max(1) as f64) as f32;
+
+    let local_degree = graph.degrees();
+    let mut local_pr = prev;
+    let old_len = local_pr.len();
+    local_pr.extend(jump[old_len..].iter().map(|&jump| (1.0 - damping) * scale * jump));
+
+    // 受影响顶点的度数和邻居变了，它们自己和邻居的残差都要重新计算。
+    // 归一化或有 seeds 时顶点数的变化、重新分配时悬挂顶点上 pr 之和的变化会影响所有顶点，这时全部重新计算
+    let candidates : Vec<Vid> = if config.normalized || config.redistribute_dangling || !config.seeds.is_empty() {
+        (start_id as Vid..end_id as Vid).collect()
+    }else {
+        let mut msgs = vec![vec![]; partitions];
+        for &u in affected {
+            msgs[rank].push(u);
+            graph.for_each_nbr(u as usize, |w| msgs[partition.vertex_partition(&w)].push(w));
+        }
+        let mut recv = exchange(msgs, communication);
+        recv.sort_unstable();
+        recv.dedup();
+        recv
+    };
+
+    // 计算初始残差需要邻居的 pr 和度数，只在开始时全量交换一次
+    let mut residual = vec![0.0; end_id - start_id];
+    {
+        let msgs = vec![(local_pr.clone(), local_degree.clone()); partitions];
+        let recv = communication.send_recv::<(Vec<f32>, Vec<Vid>)>(msgs);
+        let (global_pr, global_degree) : (Vec<f32>, Vec<Vid>) = recv.into_iter().fold((vec![], vec![]), |(mut pr, mut degree), (a, b)| {
+            pr.extend(a);
+            degree.extend(b);
+            (pr, degree)
+        });
+        let dangling : f32 = if config.redistribute_dangling {
+            global_pr.iter().zip(global_degree.iter()).filter(|(_, &degree)| degree == 0).map(|(&pr, _)| pr).sum()
+        }else {
+            0.0
+        };
+        for &v in candidates.iter() {
+            let mut sum = 0.0;
+            graph.for_each_nbr(v as usize, |u| {
+                sum += global_pr[u as usize] / global_degree[u as usize] as f32;
+            });
+            let index = v as usize - start_id;
+            residual[index] = (1.0 - damping) * scale * jump[index] + damping * (sum + dangling * jump[index]) - local_pr[index];
+        }
+    }
+
+    let mut active = candidates;
+    let mut rounds = 0;
+    loop {
+        active.retain(|&v| residual[v as usize - start_id].abs() > threshold);
+        let active_num = communication.reduce(active.len(), |a, b| a + b);
+        if active_num == 0 || rounds == config.max_iterations {
+            break;
+        }
+        rounds += 1;
+
+        let mut msgs = vec![vec![]; partitions];
+        let mut touched = vec![];
+        let mut local_dangling = 0.0;
+        for &v in active.iter() {
+            let index = v as usize - start_id;
+            let r = std::mem::take(&mut residual[index]);
+            local_pr[index] += r;
+            if local_degree[index] == 0 {
+                local_dangling += r;
+                continue;
+            }
+            let push = damping * r / local_degree[index] as f32;
+            graph.for_each_nbr(v as usize, |w| {
+                let owner = partition.vertex_partition(&w);
+                if owner == rank {
+                    residual[w as usize - start_id] += push;
+                    touched.push(w);
+                }else {
+                    msgs[owner].push((w, push));
+                }
+            });
+        }
+        for (w, push) in exchange(msgs, communication) {
+            residual[w as usize - start_id] += push;
+            touched.push(w);
+        }
+        // 悬挂顶点上新增的 pr 按跳转分布推给所有顶点
+        if config.redistribute_dangling {
+            let dangling = communication.reduce(local_dangling, |a, b| a + b);
+            if dangling != 0.0 {
+                for (index, &jump) in jump.iter().enumerate().filter(|(_, &jump)| jump > 0.0) {
+                    residual[index] += damping * dangling * jump;
+                    touched.push((start_id + index) as Vid);
+                }
+            }
+        }
+        touched.sort_unstable();
+        touched.dedup();
+        active = touched;
+    }
+    println!("delta pagerank converged after {rounds} rounds");
+
     local_pr
 }
 
@@ -107,4 +415,131 @@ mod tests {
 
         println!("rank 1: {:?}", pr);
     }
-}
\ No newline at end of file
+
+    fn check_config(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+        let sum = |pr : &Vec<f32>| communication.reduce(pr.iter().map(|&x| x as f64).sum::<f64>(), |a, b| a + b);
+
+        let mut config = PageRankConfig::default();
+        config.tolerance = 0.0;
+        config.max_iterations = 30;
+        let raw = pagerank_with_config(&graph, &config, communication);
+        assert_eq!(raw.iterations, 30);
+        assert!(raw.pr.iter().all(|x| x.is_finite()));
+
+        // 归一化的结果是非归一化结果的 1 / N
+        config.normalized = true;
+        let normalized = pagerank_with_config(&graph, &config, communication);
+        for (a, b) in raw.pr.iter().zip(normalized.pr.iter()) {
+            assert!((a / 6.0 - b).abs() < 1e-5, "raw: {:?}, normalized: {:?}", raw.pr, normalized.pr);
+        }
+        assert!(sum(&normalized.pr) < 0.99);
+
+        // 重新分配孤立顶点的 pr 后总和为 1
+        config.redistribute_dangling = true;
+        config.tolerance = 1e-6;
+        config.max_iterations = 1000;
+        let dangling = pagerank_with_config(&graph, &config, communication);
+        assert!(dangling.iterations < 1000);
+        assert!(dangling.residual < 1e-6);
+        assert!((sum(&dangling.pr) - 1.0).abs() < 1e-4, "{:?}", dangling.pr);
+    }
+
+    #[test]
+    fn config0() {
+        let communicatoner = com_for_test(42, 43, 0);
+        // 顶点 3 是孤立的
+        check_config(edges(&[(0, 1), (1, 2), (2, 0), (2, 4), (4, 5)]), &communicatoner);
+    }
+
+    #[test]
+    fn config1() {
+        let communicatoner = com_for_test(42, 43, 1);
+        check_config(vec![], &communicatoner);
+    }
+
+    fn check_weighted(edges : Vec<Edge<f32>>, communication : &impl MyMpi) {
+        let unweighted : Vec<Edge<MyEmpty>> = edges.iter().map(|edge| Edge { from : edge.from, to : edge.to, data : MyEmpty {} }).collect();
+        let expected = pagerank(NearGraph::<MyEmpty, SeqSPartition>::new(unweighted.clone(), communication), communication);
+
+        // 所有边权相同时和不带权的结果一致
+        let uniform : Vec<Edge<f32>> = edges.iter().map(|edge| Edge { from : edge.from, to : edge.to, data : 2.0 }).collect();
+        let uniform = NearGraph::<f32, SeqSPartition>::new(uniform, communication);
+        let pr = weighted_pagerank(&uniform, &PageRankConfig::default(), communication).pr;
+        for (a, b) in pr.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-4, "weighted: {:?}, unweighted: {:?}", pr, expected);
+        }
+
+        // 参数的含义和不带权的版本一致
+        let mut config = PageRankConfig::default();
+        config.damping = 0.7;
+        config.normalized = true;
+        config.redistribute_dangling = true;
+        config.seeds = vec![0, 5];
+        let pr = weighted_pagerank(&uniform, &config, communication);
+        let expected = pagerank_with_config(&NearGraph::<MyEmpty, SeqSPartition>::new(unweighted, communication), &config, communication);
+        assert_eq!(pr.iterations, expected.iterations);
+        for (a, b) in pr.pr.iter().zip(expected.pr.iter()) {
+            assert!((a - b).abs() < 1e-5, "weighted: {:?}, unweighted: {:?}", pr.pr, expected.pr);
+        }
+
+        // 星形图中心把更多的 pr 分给边权大的叶子
+        let pr = weighted_pagerank(&NearGraph::<f32, SeqSPartition>::new(edges, communication), &PageRankConfig::default(), communication).pr;
+        if communication.get_cluster_info().rank == 0 {
+            assert!(pr[1] < pr[2] && pr[2] < pr[3], "{:?}", pr);
+        }
+    }
+
+    #[test]
+    fn weighted0() {
+        let communicatoner = com_for_test(40, 41, 0);
+        let edges = weighted_edges(&[(0, 1, 1.0), (0, 2, 2.0), (0, 3, 4.0), (0, 4, 1.0), (4, 5, 1.0)]);
+        check_weighted(edges, &communicatoner);
+    }
+
+    #[test]
+    fn weighted1() {
+        let communicatoner = com_for_test(40, 41, 1);
+        check_weighted(vec![], &communicatoner);
+    }
+
+    fn check_incremental(initial : Vec<(Vid, Vid)>, inserts : Vec<(Vid, Vid)>, deletes : Vec<(Vid, Vid)>, full : Vec<(Vid, Vid)>, communication : &impl MyMpi) {
+        let mut custom = PageRankConfig::default();
+        custom.damping = 0.7;
+        custom.normalized = true;
+        custom.redistribute_dangling = true;
+        custom.seeds = vec![0, 7, 8];
+        for config in [PageRankConfig::default(), custom] {
+            let prev = pagerank_with_config(&NearGraph::<MyEmpty, SeqSPartition>::new(edges(&initial), communication), &config, communication).pr;
+
+            let mut graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges(&initial), communication);
+            let affected = graph.apply_updates(edges(&inserts), edges(&deletes), communication);
+            let pr = pagerank_incremental(&graph, prev, &affected, &config, communication);
+
+            let expected = pagerank_with_config(&NearGraph::<MyEmpty, SeqSPartition>::new(edges(&full), communication), &config, communication).pr;
+            assert_eq!(pr.len(), expected.len());
+            for (a, b) in pr.iter().zip(expected.iter()) {
+                assert!((a - b).abs() < 1e-3, "incremental: {:?}, full: {:?}", pr, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn incremental0() {
+        let communicatoner = com_for_test(30, 31, 0);
+        let ring : Vec<(Vid, Vid)> = (0..8).map(|i| (i, (i + 1) % 8)).collect();
+        let mut initial = ring.clone();
+        initial.extend([(0, 4), (2, 6)]);
+        // 更新后 3 成为孤立顶点
+        let mut full : Vec<(Vid, Vid)> = ring.into_iter().filter(|e| ![(4, 5), (2, 3), (3, 4)].contains(e)).collect();
+        full.extend([(0, 4), (1, 5), (4, 8), (8, 7), (6, 0)]);
+
+        check_incremental(initial, vec![(1, 5), (4, 8), (8, 7)], vec![(2, 6), (4, 5), (2, 3), (3, 4)], full, &communicatoner);
+    }
+
+    #[test]
+    fn incremental1() {
+        let communicatoner = com_for_test(30, 31, 1);
+        check_incremental(vec![], vec![(6, 0)], vec![], vec![], &communicatoner);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{graph::{Graph, Pratition, SeqPartition}, common::base_structure::{Vid, Eid}, parallel::server::MyMpi};
+
+use super::{exchange, assert_edge_cut};
+
+/// 未到达顶点的层数
+pub const UNREACHED : u32 = u32::MAX;
+
+/// 边数超过未访问顶点边数的 1 / ALPHA 时切换为 bottom-up
+const ALPHA : Eid = 14;
+
+/// bottom-up 时 frontier 顶点数少于顶点总数的 1 / BETA 时切换回 top-down
+const BETA : Vid = 24;
+
+#[derive(Debug)]
+pub struct BfsResult {
+    /// 本 rank 拥有的顶点的层数，源点为 0，未到达为 UNREACHED
+    pub level : Vec<u32>,
+
+    /// 本 rank 拥有的顶点在 BFS 树上的父节点，源点的父节点是自己，未到达为 Vid::MAX
+    pub parent : Vec<Vid>,
+
+    /// 最大层数 + 1
+    pub levels : u32,
+}
+
+/// 从 sources 出发的 BFS（direction-optimizing），sources 在所有 rank 上相同，要求本 rank 拥有的顶点的邻接表完整。
+/// top-down 时只把 frontier 发现的非本地顶点发给 owner；frontier 的边数相对未访问的边数足够大时
+/// 改为 bottom-up，此时每个 rank 把自己的 frontier 广播出去，由未访问的顶点在邻居中查找父节点
+pub fn bfs<G>(graph : &G, sources : &[Vid], communication : &impl MyMpi) -> BfsResult
+where
+    G : Graph + Sync,
+{
+    assert_edge_cut(graph.partition(), "bfs");
+    let partitions = communication.partitions();
+    let partition = graph.partition();
+    let start_id = partition.start_id();
+    let end_id = partition.end_id();
+    let owned = |v : Vid| v >= start_id && v < end_id;
+
+    let degree = graph.degrees();
+    let mut level = vec![UNREACHED; (end_id - start_id) as usize];
+    let mut parent = vec![Vid::MAX; (end_id - start_id) as usize];
+
+    let mut frontier : Vec<Vid> = sources.iter().cloned().filter(|&v| owned(v)).collect();
+    frontier.sort_unstable();
+    frontier.dedup();
+    for &v in frontier.iter() {
+        level[(v - start_id) as usize] = 0;
+        parent[(v - start_id) as usize] = v;
+    }
+
+    let vertex_num = communication.reduce(end_id - start_id, |a, b| a + b);
+    let mut unvisited_edges = communication.reduce(degree.iter().map(|&d| d as Eid).sum::<Eid>(), |a, b| a + b);
+    let mut bottom_up = false;
+    let mut depth = 0;
+    loop {
+        let frontier_num = communication.reduce(frontier.len() as Vid, |a, b| a + b);
+        if frontier_num == 0 {
+            break;
+        }
+        let frontier_edges = communication.reduce(frontier.iter().map(|&v| degree[(v - start_id) as usize] as Eid).sum::<Eid>(), |a, b| a + b);
+        unvisited_edges -= frontier_edges;
+        if !bottom_up && frontier_edges > unvisited_edges / ALPHA {
+            bottom_up = true;
+        }else if bottom_up && frontier_num < vertex_num / BETA {
+            bottom_up = false;
+        }
+        println!("bfs level {depth}: frontier {frontier_num}, {}", if bottom_up { "bottom-up" } else { "top-down" });
+
+        let next_level = depth + 1;
+        let found : Vec<(Vid, Vid)> = if bottom_up {
+            let msgs = vec![frontier.clone(); partitions];
+            let current : HashSet<Vid> = exchange(msgs, communication).into_iter().collect();
+            (start_id..end_id).into_par_iter().filter(|&v| level[(v - start_id) as usize] == UNREACHED).filter_map(|v| {
+                graph.find_nbr(v as usize, |w| current.contains(&w)).map(|w| (v, w))
+            }).collect()
+        }else {
+            let mut msgs = vec![vec![]; partitions];
+            for &v in frontier.iter() {
+                graph.for_each_nbr(v as usize, |w| {
+                    msgs[partition.vertex_partition(&w)].push((w, v));
+                });
+            }
+            exchange(msgs, communication)
+        };
+
+        frontier = vec![];
+        for (v, from) in found {
+            let index = (v - start_id) as usize;
+            if level[index] == UNREACHED {
+                level[index] = next_level;
+                parent[index] = from;
+                frontier.push(v);
+            }
+        }
+        depth = next_level;
+    }
+
+    BfsResult {
+        level : level,
+        parent : parent,
+        levels : depth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::{NearGraph, SeqSPartition}, common::base_structure::Edge};
+
+    fn check(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi, level : Vec<u32>, parent : Vec<Vid>, multi_level : Vec<u32>, chain_level : Vec<u32>) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+
+        let result = bfs(&graph, &[0], communication);
+        assert_eq!(result.level, level);
+        assert_eq!(result.parent, parent);
+        assert_eq!(result.levels, 5);
+
+        let result = bfs(&graph, &[0, 9], communication);
+        assert_eq!(result.level, multi_level);
+        if communication.get_cluster_info().rank == 1 {
+            // 7 可以从 6 或 8 到达
+            assert!(result.parent[0] == 6 || result.parent[0] == 8);
+            assert_eq!(result.parent[1..], [9, 9, Vid::MAX, Vid::MAX, Vid::MAX]);
+        }
+
+        // 从链的一端出发，第一层的 frontier 很小，使用 top-down
+        let result = bfs(&graph, &[9], communication);
+        assert_eq!(result.level, chain_level);
+    }
+
+    #[test]
+    fn bfs0() {
+        let communicatoner = com_for_test(46, 47, 0);
+        // 以 0 为中心的星形，6 - 7 - 8 - 9 的链，10 孤立，11 - 12 不连通
+        let edges = edges(&[(0, 1), (0, 2), (0, 3), (0, 4), (0, 5), (0, 6), (6, 7), (7, 8), (8, 9), (11, 12)]);
+        check(edges, &communicatoner, vec![0, 1, 1, 1, 1, 1, 1], vec![0, 0, 0, 0, 0, 0, 0], vec![0, 1, 1, 1, 1, 1, 1], vec![4, 5, 5, 5, 5, 5, 3]);
+    }
+
+    #[test]
+    fn bfs1() {
+        let communicatoner = com_for_test(46, 47, 1);
+        let parent = vec![6, 7, 8, Vid::MAX, Vid::MAX, Vid::MAX];
+        check(vec![], &communicatoner, vec![2, 3, 4, UNREACHED, UNREACHED, UNREACHED], parent, vec![2, 1, 0, UNREACHED, UNREACHED, UNREACHED], vec![2, 1, 0, UNREACHED, UNREACHED, UNREACHED]);
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, IndexedParallelIterator, ParallelIterator};
+
+use crate::{graph::{Graph, Pratition, SeqPartition}, common::base_structure::Vid, parallel::server::MyMpi};
+
+use super::{exchange, assert_edge_cut};
+
+#[derive(Debug)]
+pub struct KCoreResult {
+    /// 本 rank 拥有的每个顶点的 core number
+    pub core : Vec<Vid>,
+
+    /// 全局最大的 core number
+    pub max_core : Vid,
+
+    /// 交换估计值的轮数
+    pub rounds : usize,
+}
+
+/// estimates 的 h-index：最大的 h 使得至少有 h 个值不小于 h，结果不超过 upper
+fn h_index(estimates : impl Iterator<Item = Vid>, upper : Vid) -> Vid {
+    let mut count = vec![0 as Vid; upper as usize + 1];
+    for e in estimates {
+        count[e.min(upper) as usize] += 1;
+    }
+    let mut at_least = 0;
+    for h in (1..=upper).rev() {
+        at_least += count[h as usize];
+        if at_least >= h {
+            return h;
+        }
+    }
+    0
+}
+
+/// k-core 分解，按无向简单图处理：忽略自环，重复边只算一次，要求本 rank 拥有的顶点的邻接表完整。
+/// 每个顶点的估计值从度数开始，每轮更新为邻居估计值的 h-index，单调不增，收敛到 core number。
+/// 每轮只有估计值变小的顶点把新值发给持有它的邻居的 rank，远端邻居的估计值缓存在本地
+pub fn k_core<G>(graph : &G, communication : &impl MyMpi) -> KCoreResult
+where
+    G : Graph + Sync,
+{
+    assert_edge_cut(graph.partition(), "k_core");
+    let partitions = communication.partitions();
+    let rank = communication.get_cluster_info().rank;
+    let partition = graph.partition();
+    let start_id = partition.start_id();
+    let end_id = partition.end_id();
+    let owned = |v : Vid| v >= start_id && v < end_id;
+
+    let nbrs : Vec<Vec<Vid>> = (start_id..end_id).into_par_iter().map(|v| {
+        let mut nbr = vec![];
+        graph.for_each_nbr(v as usize, |w| if w != v { nbr.push(w) });
+        nbr.sort_unstable();
+        nbr.dedup();
+        nbr
+    }).collect();
+    // 每个顶点的远端邻居分布在哪些 rank 上
+    let targets : Vec<Vec<usize>> = nbrs.iter().map(|nbr| {
+        let mut ranks : Vec<usize> = nbr.iter().map(|w| partition.vertex_partition(w)).filter(|&r| r != rank).collect();
+        ranks.sort_unstable();
+        ranks.dedup();
+        ranks
+    }).collect();
+
+    let mut core : Vec<Vid> = nbrs.iter().map(|nbr| nbr.len() as Vid).collect();
+    let mut remote : HashMap<Vid, Vid> = HashMap::new();
+    let mut changed : Vec<Vid> = (start_id..end_id).collect();
+    let mut rounds = 0;
+    loop {
+        let changed_num = communication.reduce(changed.len(), |a, b| a + b);
+        if changed_num == 0 {
+            break;
+        }
+        rounds += 1;
+        println!("k-core round {rounds}: {changed_num} changed");
+
+        let mut msgs = vec![vec![]; partitions];
+        for &v in changed.iter() {
+            let index = (v - start_id) as usize;
+            for &r in targets[index].iter() {
+                msgs[r].push((v, core[index]));
+            }
+        }
+        remote.extend(exchange(msgs, communication));
+
+        let estimate = |w : Vid| if owned(w) { core[(w - start_id) as usize] } else { remote[&w] };
+        let next : Vec<Vid> = nbrs.par_iter().zip(core.par_iter()).map(|(nbr, &c)| {
+            h_index(nbr.iter().map(|&w| estimate(w)), c)
+        }).collect();
+        changed = (start_id..end_id).filter(|&v| next[(v - start_id) as usize] < core[(v - start_id) as usize]).collect();
+        core = next;
+    }
+
+    let max_core = communication.reduce(core.iter().cloned().max().unwrap_or_default(), |a, b| a.max(b));
+    KCoreResult {
+        core : core,
+        max_core : max_core,
+        rounds : rounds,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::{NearGraph, SeqSPartition}, common::base_structure::Edge};
+
+    #[test]
+    fn h_index_bound() {
+        assert_eq!(h_index(vec![3, 3, 1].into_iter(), 3), 2);
+        assert_eq!(h_index(vec![5, 5, 5].into_iter(), 2), 2);
+        assert_eq!(h_index(vec![].into_iter(), 0), 0);
+    }
+
+    fn check(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi, core : Vec<Vid>) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+        let result = k_core(&graph, communication);
+        assert_eq!(result.core, core);
+        assert_eq!(result.max_core, 3);
+    }
+
+    #[test]
+    fn kcore0() {
+        let communicatoner = com_for_test(56, 57, 0);
+        // 0 1 2 5 组成跨 rank 的 K4，3 4 6 成环，3 - 0 连接两部分，6 - 7 是悬挂边，0 - 1 重复、7 自环
+        let edges = edges(&[(0, 1), (0, 2), (0, 5), (1, 2), (1, 5), (2, 5), (1, 0), (3, 4), (4, 6), (6, 3), (3, 0), (6, 7), (7, 7)]);
+        check(edges, &communicatoner, vec![3, 3, 3, 2, 2]);
+    }
+
+    #[test]
+    fn kcore1() {
+        let communicatoner = com_for_test(56, 57, 1);
+        check(vec![], &communicatoner, vec![3, 2, 1]);
+    }
+}
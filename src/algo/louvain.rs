@@ -0,0 +1,353 @@
+use std::collections::{HashMap, BTreeMap};
+
+use bincode::{Encode, Decode};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{graph::{Graph, SeqPartition, NearGraph, GraphInfo, clean::{CleanOption, combine}}, common::base_structure::{Vid, Edge}, parallel::server::MyMpi, traits::Weight, io::generator::Rng};
+
+use super::{exchange, fetch, assert_edge_cut};
+
+use std::fmt::Debug;
+
+/// louvain 的参数
+#[derive(Debug, Clone)]
+pub struct LouvainConfig {
+    /// 最多粗化的层数
+    pub max_levels : usize,
+
+    /// 每一层局部移动的最多轮数
+    pub max_iterations : usize,
+
+    /// 一层的模块度比上一层提高不超过它时停止
+    pub tolerance : f64,
+
+    /// 局部移动时随机分组用的种子
+    pub seed : u64,
+}
+
+impl LouvainConfig {
+    pub fn default() -> Self {
+        LouvainConfig {
+            max_levels : 10,
+            max_iterations : 20,
+            tolerance : 1e-6,
+            seed : 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LouvainResult {
+    /// 本 rank 拥有的每个顶点最终所在的社区，社区从 0 开始连续编号
+    pub community : Vec<Vid>,
+
+    /// 每一层结束后本 rank 拥有的每个顶点所在的社区，即这一层粗化后图中的顶点 id
+    pub levels : Vec<Vec<Vid>>,
+
+    /// 每一层结束后的模块度
+    pub level_modularity : Vec<f64>,
+
+    /// 最终的模块度
+    pub modularity : f64,
+}
+
+/// 一层局部移动的结果
+struct Moving {
+    /// 本 rank 拥有的顶点所在的社区，社区用这一层的顶点 id 表示
+    label : Vec<Vid>,
+
+    /// 远端邻居所在的社区
+    remote : HashMap<Vid, Vid>,
+
+    modularity : f64,
+
+    moved : usize,
+}
+
+/// 局部移动：每个顶点依次尝试移到邻居所在的社区中模块度增益最大的一个。
+/// 每轮把顶点随机分成两组先后移动，每组移动后所有 rank 交换社区 totals 的变化量，
+/// 只有换了社区的顶点把新社区发给持有它的邻居的 rank
+fn local_moving<EDATA, PART>(graph : &NearGraph<EDATA, PART>, config : &LouvainConfig, level : usize, communication : &impl MyMpi) -> Moving
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    let partitions = communication.partitions();
+    let partition = graph.partition();
+    let start_id = partition.start_id();
+    let end_id = partition.end_id();
+    let len = (end_id - start_id) as usize;
+    let owned = |v : Vid| v >= start_id && v < end_id;
+
+    // 自环在邻接表中出现两次，度数里计两次
+    let k : Vec<f64> = (start_id..end_id).into_par_iter().map(|v| {
+        let mut k = 0.0;
+        graph.for_each_weighted_nbr(v as usize, |_, w| k += w as f64);
+        k
+    }).collect();
+    let m2 = communication.reduce(k.iter().sum::<f64>(), |a, b| a + b);
+
+    let targets : Vec<Vec<usize>> = (start_id..end_id).map(|v| {
+        let mut ranks = vec![];
+        graph.for_each_nbr(v as usize, |w| if !owned(w) { ranks.push(partition.vertex_partition(&w)) });
+        ranks.sort_unstable();
+        ranks.dedup();
+        ranks
+    }).collect();
+
+    // 每个社区的度数之和，每个 rank 上都有完整的一份
+    let mut tot = vec![0.0f64; graph.graph_info.vertex_num as usize];
+    let degrees : Vec<(Vid, f64)> = k.iter().enumerate().map(|(i, &k)| (start_id + i as Vid, k)).collect();
+    for (v, k) in exchange(vec![degrees; partitions], communication) {
+        tot[v as usize] += k;
+    }
+
+    let mut label : Vec<Vid> = (start_id..end_id).collect();
+    let mut remote : HashMap<Vid, Vid> = HashMap::new();
+    let mut pending : Vec<usize> = (0..len).collect();
+    let mut moved = 0;
+    let send = |pending : &Vec<usize>, label : &Vec<Vid>, remote : &mut HashMap<Vid, Vid>| {
+        let mut msgs = vec![vec![]; partitions];
+        for &index in pending.iter() {
+            for &r in targets[index].iter() {
+                msgs[r].push((start_id + index as Vid, label[index]));
+            }
+        }
+        remote.extend(exchange(msgs, communication));
+    };
+
+    for iteration in 0..config.max_iterations {
+        if m2 == 0.0 {
+            break;
+        }
+        let mut moved_num = 0;
+        let key = config.seed ^ ((level as u64) << 48) ^ ((iteration as u64) << 32);
+        let group : Vec<u64> = (start_id..end_id).map(|v| Rng::new(key | v as u64).next_u64() & 1).collect();
+        for g in 0..2 {
+            send(&pending, &label, &mut remote);
+
+            let community = |w : Vid| if owned(w) { label[(w - start_id) as usize] } else { remote[&w] };
+            let moves : Vec<(usize, Vid)> = (0..len).into_par_iter().filter(|&index| group[index] == g).filter_map(|index| {
+                let v = start_id + index as Vid;
+                let own = label[index];
+                let mut links : BTreeMap<Vid, f64> = BTreeMap::new();
+                graph.for_each_weighted_nbr(v as usize, |w, weight| {
+                    if w != v {
+                        *links.entry(community(w)).or_default() += weight as f64;
+                    }
+                });
+                // 移到社区 c 的增益（省略了公共的系数 1 / m）
+                let gain = |c : Vid, link : f64| {
+                    let tot_c = if c == own { tot[c as usize] - k[index] } else { tot[c as usize] };
+                    link - k[index] * tot_c / m2
+                };
+                let mut best = (own, gain(own, links.get(&own).cloned().unwrap_or_default()));
+                for (&c, &link) in links.iter() {
+                    let g = gain(c, link);
+                    if g > best.1 + 1e-12 {
+                        best = (c, g);
+                    }
+                }
+                if best.0 == own { None } else { Some((index, best.0)) }
+            }).collect();
+
+            let mut deltas = vec![];
+            for &(index, c) in moves.iter() {
+                deltas.push((label[index], -k[index]));
+                deltas.push((c, k[index]));
+                label[index] = c;
+            }
+            for (c, delta) in exchange(vec![deltas; partitions], communication) {
+                tot[c as usize] += delta;
+            }
+            pending = moves.into_iter().map(|(index, _)| index).collect();
+            moved_num += pending.len();
+        }
+
+        let moved_num = communication.reduce(moved_num, |a, b| a + b);
+        println!("louvain level {level} iteration {iteration}: {moved_num} moved");
+        moved += moved_num;
+        if moved_num == 0 {
+            break;
+        }
+    }
+    send(&pending, &label, &mut remote);
+
+    let community = |w : Vid| if owned(w) { label[(w - start_id) as usize] } else { remote[&w] };
+    let internal : f64 = (start_id..end_id).map(|v| {
+        let mut internal = 0.0;
+        graph.for_each_weighted_nbr(v as usize, |w, weight| {
+            if community(w) == community(v) {
+                internal += weight as f64;
+            }
+        });
+        internal
+    }).sum();
+    let internal = communication.reduce(internal, |a, b| a + b);
+    let modularity = if m2 == 0.0 {
+        0.0
+    }else {
+        internal / m2 - tot.iter().map(|t| (t / m2) * (t / m2)).sum::<f64>()
+    };
+
+    Moving {
+        label : label,
+        remote : remote,
+        modularity : modularity,
+        moved : moved,
+    }
+}
+
+/// 把每个社区粗化为一个顶点，社区之间的边权合并为和，社区内部的边变成自环。
+/// 返回粗化后的图和 current 中每个顶点（这一层的顶点 id）在粗化后的图中的 id
+fn coarsen<EDATA, PART>(graph : &NearGraph<EDATA, PART>, moving : &Moving, current : &[Vid], communication : &impl MyMpi) -> (NearGraph<f32, PART>, Vec<Vid>)
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    let partition = graph.partition();
+    let start_id = partition.start_id();
+    let end_id = partition.end_id();
+    let owned = |v : Vid| v >= start_id && v < end_id;
+    let community = |w : Vid| if owned(w) { moving.label[(w - start_id) as usize] } else { moving.remote[&w] };
+
+    let mut used = moving.label.clone();
+    used.sort_unstable();
+    used.dedup();
+    let mut used = exchange(vec![used; communication.partitions()], communication);
+    used.sort_unstable();
+    used.dedup();
+    let new_id = |c : Vid| used.binary_search(&c).unwrap() as Vid;
+
+    let edges : Vec<Edge<f32>> = (start_id..end_id).into_par_iter().flat_map_iter(|u| {
+        let mut res = vec![];
+        graph.for_each_unique_edge(u, |from, to, data| {
+            res.push(Edge { from : new_id(community(from)), to : new_id(community(to)), data : data.weight() });
+        });
+        res
+    }).collect();
+
+    let graph_info = GraphInfo { vertex_num : used.len() as Vid, edge_num : 0 };
+    let mut option = CleanOption::default();
+    option.merge = Some(combine::sum::<f32>);
+    option.merge_reverse = true;
+    let coarse = NearGraph::<f32, PART>::build(graph_info, edges, communication, &option);
+
+    let next = fetch(graph, current.to_vec(), |v| new_id(moving.label[(v - start_id) as usize]), communication);
+    (coarse, current.iter().map(|v| next[v]).collect())
+}
+
+/// 一层 louvain：局部移动后如果有顶点移动且模块度比 best 提高超过 tolerance，就粗化出下一层的图
+fn louvain_level<EDATA, PART>(graph : &NearGraph<EDATA, PART>, config : &LouvainConfig, level : usize, current : &[Vid], best : f64, communication : &impl MyMpi) -> (f64, Option<(NearGraph<f32, PART>, Vec<Vid>)>)
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    let moving = local_moving(graph, config, level, communication);
+    println!("louvain level {level}: modularity {}, {} moves", moving.modularity, moving.moved);
+    if moving.moved == 0 || moving.modularity - best <= config.tolerance {
+        return (moving.modularity, None);
+    }
+    (moving.modularity, Some(coarsen(graph, &moving, current, communication)))
+}
+
+/// 分布式 louvain 社区发现，要求本 rank 拥有的顶点的邻接表完整，边权为 Weight。
+/// 每一层先做局部移动，再把社区粗化为新的 NearGraph（社区之间的平行边合并为权重之和），
+/// 在粗化后的图上重复，直到没有顶点移动、模块度不再提高或达到 max_levels
+pub fn louvain<EDATA, PART>(graph : &NearGraph<EDATA, PART>, config : &LouvainConfig, communication : &impl MyMpi) -> LouvainResult
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    assert_edge_cut(graph.partition(), "louvain");
+    let partition = graph.partition();
+    let mut current : Vec<Vid> = (partition.start_id()..partition.end_id()).collect();
+    let mut coarse : Option<NearGraph<f32, PART>> = None;
+    let mut levels = vec![];
+    let mut level_modularity = vec![];
+    let mut modularity = f64::NEG_INFINITY;
+
+    for level in 0..config.max_levels {
+        let (q, next) = match &coarse {
+            None => louvain_level(graph, config, level, &current, modularity, communication),
+            Some(coarse) => louvain_level::<f32, PART>(coarse, config, level, &current, modularity, communication),
+        };
+        match next {
+            Some((next, ids)) => {
+                modularity = q;
+                level_modularity.push(q);
+                levels.push(ids.clone());
+                current = ids;
+                coarse = Some(next);
+            }
+            None => {
+                if levels.is_empty() {
+                    modularity = q;
+                }
+                break;
+            }
+        }
+    }
+
+    LouvainResult {
+        community : current,
+        levels : levels,
+        level_modularity : level_modularity,
+        modularity : modularity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::SeqSPartition};
+
+    fn check(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+        let result = louvain(&graph, &LouvainConfig::default(), communication);
+
+        // m = 17，三个社区的 2 * 内部边权为 12、12、6，度数之和为 13、14、7
+        let expected = 30.0 / 34.0 - (13.0 * 13.0 + 14.0 * 14.0 + 7.0 * 7.0) / (34.0 * 34.0);
+        assert!((result.modularity - expected).abs() < 1e-9, "modularity: {}", result.modularity);
+        assert!(!result.levels.is_empty());
+        assert_eq!(result.levels.last().unwrap(), &result.community);
+        assert_eq!(*result.level_modularity.last().unwrap(), result.modularity);
+        assert!(result.level_modularity.windows(2).all(|q| q[0] < q[1]));
+
+        let all = communication.gather(result.community, 0);
+        if communication.get_cluster_info().rank == 0 {
+            let all : Vec<Vid> = all.into_iter().flatten().collect();
+            assert!(all[0..4].iter().all(|&c| c == all[0]));
+            assert!(all[4..8].iter().all(|&c| c == all[4]));
+            assert!(all[8..11].iter().all(|&c| c == all[8]));
+            let mut distinct = vec![all[0], all[4], all[8]];
+            distinct.sort_unstable();
+            assert_eq!(distinct, vec![0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn louvain0() {
+        let communicatoner = com_for_test(60, 61, 0);
+        // 两个 K4：0 1 2 3 和跨 rank 的 4 5 6 7，三角形 8 9 10，由 3 - 4、7 - 8 连接
+        let mut list = vec![(3, 4), (7, 8), (8, 9), (9, 10), (10, 8)];
+        for clique in [[0, 1, 2, 3], [4, 5, 6, 7]] {
+            for i in 0..4 {
+                for j in i + 1..4 {
+                    list.push((clique[i], clique[j]));
+                }
+            }
+        }
+        check(edges(&list), &communicatoner);
+    }
+
+    #[test]
+    fn louvain1() {
+        let communicatoner = com_for_test(60, 61, 1);
+        check(vec![], &communicatoner);
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::{HashMap, BTreeMap};
+
+use bincode::{Encode, Decode};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{graph::{Graph, SeqPartition, NearGraph}, common::base_structure::{Vid, Edge}, parallel::server::MyMpi, traits::Weight, io::generator::Rng};
+
+use super::{exchange, gather_sizes, assert_edge_cut};
+
+use std::fmt::Debug;
+
+/// 标签传播的参数
+#[derive(Debug, Clone)]
+pub struct LpaConfig {
+    pub max_iterations : usize,
+
+    /// 一轮中标签没有变化的顶点比例不低于它时停止，为 1 时要求完全收敛
+    pub stable_fraction : f64,
+
+    /// 为 true 时按 Weight 给邻居的标签加权，否则每条边计 1
+    pub weighted : bool,
+
+    /// 为 true 时每轮把顶点随机分成两组先后更新，后一组能看到前一组的新标签，避免同步更新在二分结构上来回振荡
+    pub semi_synchronous : bool,
+
+    /// 打破平局用的随机种子，结果只由种子决定，与 rank 的个数无关
+    pub seed : u64,
+}
+
+impl LpaConfig {
+    pub fn default() -> Self {
+        LpaConfig {
+            max_iterations : 100,
+            stable_fraction : 1.0,
+            weighted : true,
+            semi_synchronous : true,
+            seed : 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LpaResult {
+    /// 本 rank 拥有的每个顶点的社区标签
+    pub label : Vec<Vid>,
+
+    pub iterations : usize,
+
+    /// (标签, 社区大小)，按标签升序，只在 rank 0 上为 Some
+    pub sizes : Option<Vec<(Vid, Vid)>>,
+}
+
+/// 标签传播社区发现，要求本 rank 拥有的顶点的邻接表完整。每个顶点的初始标签是自己的 id，
+/// 每次更新为邻居中权重之和最大的标签（忽略自环）：当前标签在最大者之中时保持不变，
+/// 否则由 (seed, 轮数, 顶点) 决定的随机数在并列的标签中选一个。
+/// 每次更新前只有标签变化了的顶点把新标签发给持有它的邻居的 rank
+pub fn label_propagation<EDATA, PART>(graph : &NearGraph<EDATA, PART>, config : &LpaConfig, communication : &impl MyMpi) -> LpaResult
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    assert_edge_cut(graph.partition(), "label_propagation");
+    let partitions = communication.partitions();
+    let rank = communication.get_cluster_info().rank;
+    let partition = graph.partition();
+    let start_id = partition.start_id();
+    let end_id = partition.end_id();
+    let len = (end_id - start_id) as usize;
+    let owned = |v : Vid| v >= start_id && v < end_id;
+    let vertex_num = communication.reduce(len, |a, b| a + b);
+
+    let targets : Vec<Vec<usize>> = (start_id..end_id).map(|v| {
+        let mut ranks = vec![];
+        graph.for_each_nbr(v as usize, |w| if !owned(w) { ranks.push(partition.vertex_partition(&w)) });
+        ranks.sort_unstable();
+        ranks.dedup();
+        debug_assert!(!ranks.contains(&rank));
+        ranks
+    }).collect();
+    let groups = if config.semi_synchronous { 2 } else { 1 };
+
+    let mut label : Vec<Vid> = (start_id..end_id).collect();
+    let mut remote : HashMap<Vid, Vid> = HashMap::new();
+    let mut pending : Vec<usize> = (0..len).collect();
+    let mut iterations = 0;
+    while iterations < config.max_iterations {
+        iterations += 1;
+        // 每轮重新随机分组，固定的分组可能把一个对称的结构整个分在同一组里
+        let group : Vec<usize> = (start_id..end_id).map(|v| {
+            (Rng::new(config.seed ^ ((iterations as u64) << 32 | v as u64)).next_u64() % groups as u64) as usize
+        }).collect();
+        let mut changed_num = 0;
+        for g in 0..groups {
+            let mut msgs = vec![vec![]; partitions];
+            for &index in pending.iter() {
+                for &r in targets[index].iter() {
+                    msgs[r].push((start_id + index as Vid, label[index]));
+                }
+            }
+            remote.extend(exchange(msgs, communication));
+
+            let current = |w : Vid| if owned(w) { label[(w - start_id) as usize] } else { remote[&w] };
+            let updates : Vec<(usize, Vid)> = (0..len).into_par_iter().filter(|&index| group[index] == g).filter_map(|index| {
+                let v = start_id + index as Vid;
+                let mut weights : BTreeMap<Vid, f32> = BTreeMap::new();
+                graph.for_each_weighted_nbr(v as usize, |w, weight| {
+                    if w != v {
+                        *weights.entry(current(w)).or_default() += if config.weighted { weight } else { 1.0 };
+                    }
+                });
+                let max = weights.values().cloned().fold(f32::NEG_INFINITY, f32::max);
+                let ties : Vec<Vid> = weights.into_iter().filter(|&(_, weight)| weight == max).map(|(l, _)| l).collect();
+                if ties.is_empty() || ties.contains(&label[index]) {
+                    return None;
+                }
+                let mut rng = Rng::new(!config.seed ^ ((iterations as u64) << 32 | v as u64));
+                Some((index, ties[rng.below(ties.len() as u64) as usize]))
+            }).collect();
+
+            pending = updates.iter().map(|&(index, _)| index).collect();
+            for (index, l) in updates {
+                label[index] = l;
+            }
+            changed_num += pending.len();
+        }
+
+        let changed_num = communication.reduce(changed_num, |a, b| a + b);
+        println!("label propagation iteration {iterations}: {changed_num} changed");
+        if changed_num as f64 <= (1.0 - config.stable_fraction) * vertex_num as f64 {
+            break;
+        }
+    }
+
+    let sizes = gather_sizes(&label, communication);
+    LpaResult {
+        label : label,
+        iterations : iterations,
+        sizes : sizes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::weighted_edges, graph::SeqSPartition};
+
+    fn check(list : Vec<(Vid, Vid, f32)>, communication : &impl MyMpi) {
+        let graph = NearGraph::<f32, SeqSPartition>::new(weighted_edges(&list), communication);
+        let rank = communication.get_cluster_info().rank;
+
+        let config = LpaConfig::default();
+        let result = label_propagation(&graph, &config, communication);
+        assert!(result.iterations < config.max_iterations);
+        // 相同的种子得到相同的结果
+        assert_eq!(label_propagation(&graph, &config, communication).label, result.label);
+
+        let all = communication.gather(result.label, 0);
+        if rank == 0 {
+            let all : Vec<Vid> = all.into_iter().flatten().collect();
+            assert!(all[0..4].iter().all(|&l| l == all[0]));
+            assert!(all[4..8].iter().all(|&l| l == all[4]));
+            assert_ne!(all[0], all[4]);
+            // 8 与 5 之间的边权更大
+            assert_eq!(all[8], all[4]);
+            let mut sizes : Vec<Vid> = result.sizes.unwrap().into_iter().map(|(_, size)| size).collect();
+            sizes.sort_unstable();
+            assert_eq!(sizes, vec![4, 5]);
+        }else {
+            assert!(result.sizes.is_none());
+        }
+
+        let mut config = LpaConfig::default();
+        config.max_iterations = 1;
+        config.semi_synchronous = false;
+        assert_eq!(label_propagation(&graph, &config, communication).iterations, 1);
+    }
+
+    #[test]
+    fn lpa0() {
+        let communicatoner = com_for_test(58, 59, 0);
+        // 两个 K4：0 1 2 3 和跨 rank 的 4 5 6 7，由 3 - 4 弱连接；8 同时连向 0 和 5
+        let mut list = vec![(3, 4, 0.1), (8, 0, 0.5), (8, 5, 2.0)];
+        for clique in [[0, 1, 2, 3], [4, 5, 6, 7]] {
+            for i in 0..4 {
+                for j in i + 1..4 {
+                    list.push((clique[i], clique[j], 1.0));
+                }
+            }
+        }
+        check(list, &communicatoner);
+    }
+
+    #[test]
+    fn lpa1() {
+        let communicatoner = com_for_test(58, 59, 1);
+        check(vec![], &communicatoner);
+    }
+}
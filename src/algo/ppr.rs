@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use crate::{graph::{Graph, Pratition, SeqPartition}, common::base_structure::Vid, parallel::server::MyMpi};
+
+use super::{PageRankConfig, pagerank_with_config, exchange, assert_edge_cut};
+
+/// 把各个 rank 上的 (vid, score) 汇总到 rank 0，按 score 降序取前 k 个，score 相同时 vid 小的在前。
+/// 只在 rank 0 上返回 Some
+pub fn top_k(mut local : Vec<(Vid, f32)>, k : usize, communication : &impl MyMpi) -> Option<Vec<(Vid, f32)>> {
+    let order = |a : &(Vid, f32), b : &(Vid, f32)| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0));
+    local.sort_by(order);
+    local.truncate(k);
+
+    let recv = communication.gather(local, 0);
+    if communication.get_cluster_info().rank != 0 {
+        return None;
+    }
+    let mut all : Vec<(Vid, f32)> = recv.into_iter().flatten().collect();
+    all.sort_by(order);
+    all.truncate(k);
+    Some(all)
+}
+
+/// 从 seeds 出发的 personalized pagerank，用幂迭代计算，跳转和度数为 0 的顶点上的 pr 都只回到 seeds。
+/// seeds 在所有 rank 上相同，结果为归一化后 pr 最大的 k 个顶点，只在 rank 0 上返回 Some
+pub fn personalized_pagerank<G>(graph : &G, seeds : Vec<Vid>, k : usize, communication : &impl MyMpi) -> Option<Vec<(Vid, f32)>>
+where
+    G : Graph + Sync,
+{
+    let mut config = PageRankConfig::default();
+    config.normalized = true;
+    config.redistribute_dangling = true;
+    config.seeds = seeds;
+    let result = pagerank_with_config(graph, &config, communication);
+
+    let start_id = graph.partition().start_id();
+    let local = result.pr.into_iter().enumerate()
+        .filter(|&(_, pr)| pr > 0.0)
+        .map(|(i, pr)| (start_id + i as Vid, pr))
+        .collect();
+    top_k(local, k, communication)
+}
+
+/// 前向推送（Andersen–Chung–Lang）近似计算 personalized pagerank，只访问 seeds 附近的顶点。
+/// 每个残差 r(v) > epsilon * deg(v) 的顶点把 (1 - damping) * r(v) 留给自己，其余按度数平均推给邻居，
+/// 推给其他 rank 上顶点的残差通过 send_recv 发给 owner。度数为 0 的顶点保留全部残差。
+/// seeds 在所有 rank 上相同，结果同 personalized_pagerank
+pub fn ppr_push<G>(graph : &G, seeds : Vec<Vid>, damping : f32, epsilon : f32, k : usize, communication : &impl MyMpi) -> Option<Vec<(Vid, f32)>>
+where
+    G : Graph + Sync,
+{
+    assert_edge_cut(graph.partition(), "ppr_push");
+    let partitions = communication.partitions();
+    let rank = communication.get_cluster_info().rank;
+    let partition = graph.partition();
+    let owned = |v : Vid| v >= partition.start_id() && v < partition.end_id();
+    let degree = |v : Vid| {
+        let mut degree = 0;
+        graph.for_each_nbr(v as usize, |_| degree += 1);
+        degree
+    };
+
+    let mut seeds = seeds;
+    seeds.sort_unstable();
+    seeds.dedup();
+    let mut p : HashMap<Vid, f32> = HashMap::new();
+    let mut r : HashMap<Vid, f32> = HashMap::new();
+    let mut active : Vec<Vid> = seeds.iter().cloned().filter(|&v| owned(v)).collect();
+    active.iter().for_each(|&v| {
+        r.insert(v, 1.0 / seeds.len() as f32);
+    });
+
+    let mut rounds = 0;
+    loop {
+        active.retain(|v| r.get(v).map_or(false, |&x| x > epsilon * degree(*v).max(1) as f32));
+        if communication.reduce(active.len(), |a, b| a + b) == 0 {
+            break;
+        }
+        rounds += 1;
+
+        let mut msgs = vec![vec![]; partitions];
+        let mut touched = vec![];
+        for &v in active.iter() {
+            let residual = r.remove(&v).unwrap_or_default();
+            let degree = degree(v);
+            if degree == 0 {
+                *p.entry(v).or_default() += residual;
+                continue;
+            }
+            *p.entry(v).or_default() += (1.0 - damping) * residual;
+            let push = damping * residual / degree as f32;
+            graph.for_each_nbr(v as usize, |w| {
+                if owned(w) {
+                    *r.entry(w).or_default() += push;
+                    touched.push(w);
+                }else {
+                    msgs[partition.vertex_partition(&w)].push((w, push));
+                }
+            });
+        }
+        debug_assert!(msgs[rank].is_empty());
+        for (w, push) in exchange(msgs, communication) {
+            *r.entry(w).or_default() += push;
+            touched.push(w);
+        }
+        touched.sort_unstable();
+        touched.dedup();
+        active = touched;
+    }
+    println!("ppr push finished after {rounds} rounds, touched {} vertices", p.len());
+
+    top_k(p.into_iter().collect(), k, communication)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::{NearGraph, SeqSPartition}, common::base_structure::Edge};
+
+    fn check(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+
+        let power = personalized_pagerank(&graph, vec![0], 4, communication);
+        let push = ppr_push(&graph, vec![0], 0.85, 1e-8, 4, communication);
+        let far = ppr_push(&graph, vec![7], 0.85, 0.02, 100, communication);
+        if communication.get_cluster_info().rank != 0 {
+            assert!(power.is_none() && push.is_none() && far.is_none());
+            return;
+        }
+
+        let (power, push) = (power.unwrap(), push.unwrap());
+        assert_eq!(power.len(), 4);
+        assert_eq!(power[0].0, 0);
+        assert_eq!(power.iter().map(|x| x.0).collect::<Vec<_>>(), push.iter().map(|x| x.0).collect::<Vec<_>>());
+        for (a, b) in power.iter().zip(push.iter()) {
+            assert!((a.1 - b.1).abs() < 1e-3, "power: {:?}, push: {:?}", power, push);
+        }
+
+        // epsilon 较大时只访问种子附近的顶点
+        let far = far.unwrap();
+        assert!(far.iter().any(|&(v, _)| v == 7));
+        assert!(far.iter().all(|&(v, _)| v >= 2), "{:?}", far);
+    }
+
+    #[test]
+    fn ppr0() {
+        let communicatoner = com_for_test(44, 45, 0);
+        // 两个三角形由 2 - 3 相连，再接一条链 5 - 6 - 7
+        let edges = edges(&[(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 5), (5, 3), (5, 6), (6, 7)]);
+        check(edges, &communicatoner);
+    }
+
+    #[test]
+    fn ppr1() {
+        let communicatoner = com_for_test(44, 45, 1);
+        check(vec![], &communicatoner);
+    }
+}
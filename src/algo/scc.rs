@@ -0,0 +1,286 @@
+use bincode::{Encode, Decode};
+use rayon::iter::IntoParallelIterator;
+
+use crate::{graph::{Graph, SeqPartition, NearGraph}, common::base_structure::{Vid, Edge}, parallel::server::MyMpi};
+
+use super::{exchange, assert_edge_cut};
+
+use std::fmt::Debug;
+
+/// 未分配强连通分量的顶点
+const UNASSIGNED : Vid = Vid::MAX;
+
+#[derive(Debug)]
+pub struct SccResult {
+    /// 本 rank 拥有的顶点所在强连通分量的编号，即分量中最小的顶点 id
+    pub component : Vec<Vid>,
+
+    /// 全局的强连通分量数
+    pub components : Vid,
+
+    /// trim + 染色的外层轮数
+    pub rounds : usize,
+}
+
+/// 强连通分量的计算状态，只保存本 rank 拥有的顶点
+struct Scc<'a, EDATA, PART>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    graph : &'a NearGraph<EDATA, PART>,
+    start_id : Vid,
+    component : Vec<Vid>,
+
+    /// 仍未分配的入边、出边邻居个数，不含自环
+    in_degree : Vec<Vid>,
+    out_degree : Vec<Vid>,
+}
+
+impl<'a, EDATA, PART> Scc<'a, EDATA, PART>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    fn new(graph : &'a NearGraph<EDATA, PART>) -> Self {
+        let partition = graph.partition();
+        let start_id = partition.start_id();
+        let end_id = partition.end_id();
+        let count = |v : Vid, out : bool| graph.nbr(v as usize).iter().filter(|edge| edge.is_out() == out && edge.to != v).count() as Vid;
+        Scc {
+            graph : graph,
+            start_id : start_id,
+            component : vec![UNASSIGNED; (end_id - start_id) as usize],
+            in_degree : (start_id..end_id).map(|v| count(v, false)).collect(),
+            out_degree : (start_id..end_id).map(|v| count(v, true)).collect(),
+        }
+    }
+
+    fn index(&self, v : Vid) -> usize {
+        (v - self.start_id) as usize
+    }
+
+    fn active(&self) -> Vec<Vid> {
+        (0..self.component.len()).filter(|&i| self.component[i] == UNASSIGNED).map(|i| self.start_id + i as Vid).collect()
+    }
+
+    /// 把 (顶点, 分量) 标记为已分配，并通知邻居的 owner 减少对应的入度、出度，
+    /// 返回本 rank 上入度或出度因此变为 0 的未分配顶点
+    fn assign(&mut self, assigned : Vec<(Vid, Vid)>, communication : &impl MyMpi) -> Vec<Vid> {
+        let partition = self.graph.partition();
+        let mut msgs = vec![vec![]; communication.partitions()];
+        for &(v, component) in assigned.iter() {
+            let index = self.index(v);
+            self.component[index] = component;
+            for edge in self.graph.nbr(v as usize).iter().filter(|edge| edge.to != v) {
+                // 邻居 w 看到的方向与本端相反
+                msgs[partition.vertex_partition(&edge.to)].push((edge.to, !edge.is_out()));
+            }
+        }
+
+        let mut trivial = vec![];
+        for (w, out) in exchange(msgs, communication) {
+            let index = self.index(w);
+            if self.component[index] != UNASSIGNED {
+                continue;
+            }
+            let degree = if out { &mut self.out_degree[index] } else { &mut self.in_degree[index] };
+            *degree -= 1;
+            if *degree == 0 {
+                trivial.push(w);
+            }
+        }
+        trivial.sort_unstable();
+        trivial.dedup();
+        trivial
+    }
+
+    /// 反复去掉入度或出度为 0 的顶点，它们各自是一个平凡的强连通分量
+    fn trim(&mut self, communication : &impl MyMpi) {
+        let mut trivial : Vec<Vid> = self.active().into_iter().filter(|&v| {
+            let index = self.index(v);
+            self.in_degree[index] == 0 || self.out_degree[index] == 0
+        }).collect();
+        while communication.reduce(trivial.len(), |a, b| a + b) > 0 {
+            let assigned = trivial.into_iter().filter(|&v| self.component[self.index(v)] == UNASSIGNED).map(|v| (v, v)).collect();
+            trivial = self.assign(assigned, communication);
+        }
+    }
+
+    /// 沿出边传播最小的颜色直到不再变化。颜色等于自身的顶点是根，
+    /// 根沿入边在同色的顶点中能到达的部分就是根所在的强连通分量
+    fn color(&mut self, communication : &impl MyMpi) {
+        let partition = self.graph.partition();
+        let partitions = communication.partitions();
+        let graph = self.graph;
+
+        let mut color = vec![UNASSIGNED; self.component.len()];
+        let mut changed = self.active();
+        for &v in changed.iter() {
+            color[self.index(v)] = v;
+        }
+        while communication.reduce(changed.len(), |a, b| a + b) > 0 {
+            let mut msgs = vec![vec![]; partitions];
+            for &v in changed.iter() {
+                let c = color[self.index(v)];
+                graph.for_each_out_nbr(v as usize, |w| msgs[partition.vertex_partition(&w)].push((w, c)));
+            }
+            changed = vec![];
+            for (w, c) in exchange(msgs, communication) {
+                let index = self.index(w);
+                if self.component[index] == UNASSIGNED && c < color[index] {
+                    color[index] = c;
+                    changed.push(w);
+                }
+            }
+            changed.sort_unstable();
+            changed.dedup();
+        }
+
+        let mut frontier : Vec<Vid> = self.active().into_iter().filter(|&v| color[self.index(v)] == v).collect();
+        let mut found = vec![false; self.component.len()];
+        let mut assigned = vec![];
+        while communication.reduce(frontier.len(), |a, b| a + b) > 0 {
+            let mut msgs = vec![vec![]; partitions];
+            for &v in frontier.iter() {
+                let index = self.index(v);
+                found[index] = true;
+                assigned.push((v, color[index]));
+                graph.for_each_in_nbr(v as usize, |u| msgs[partition.vertex_partition(&u)].push((u, color[index])));
+            }
+            frontier = vec![];
+            for (u, c) in exchange(msgs, communication) {
+                let index = self.index(u);
+                if self.component[index] == UNASSIGNED && !found[index] && color[index] == c {
+                    found[index] = true;
+                    frontier.push(u);
+                }
+            }
+            frontier.sort_unstable();
+            frontier.dedup();
+        }
+        self.assign(assigned, communication);
+    }
+}
+
+/// 有向图的强连通分量，边的方向为构图时 Edge 的 from -> to，要求本 rank 拥有的顶点的邻接表完整。
+/// 每轮先反复 trim 掉入度或出度为 0 的顶点，再对剩下的顶点做一次前向染色 + 反向 BFS，
+/// 每次至少确定一个非平凡的强连通分量，直到所有顶点都被分配
+pub fn scc<EDATA, PART>(graph : &NearGraph<EDATA, PART>, communication : &impl MyMpi) -> SccResult
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    assert_edge_cut(graph.partition(), "scc");
+    let mut scc = Scc::new(graph);
+    let mut rounds = 0;
+    loop {
+        scc.trim(communication);
+        let remaining = communication.reduce(scc.active().len(), |a, b| a + b);
+        if remaining == 0 {
+            break;
+        }
+        rounds += 1;
+        println!("scc round {rounds}: {remaining} vertices remaining after trim");
+        scc.color(communication);
+    }
+
+    let start_id = scc.start_id;
+    let roots = scc.component.iter().enumerate().filter(|&(i, &c)| c == start_id + i as Vid).count() as Vid;
+    SccResult {
+        components : communication.reduce(roots, |a, b| a + b),
+        component : scc.component,
+        rounds : rounds,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::{SeqSPartition, clean::{CleanOption, combine}}};
+
+    fn check(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi, component : Vec<Vid>) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+        let result = scc(&graph, communication);
+        assert_eq!(result.component, component);
+        assert_eq!(result.components, 5);
+        assert!(result.rounds >= 2);
+    }
+
+    // 环 0 -> 1 -> 2 -> 0 指向跨 rank 的环 3 -> 8 -> 9 -> 3 和 6 -> 7 -> 11 -> 6，
+    // 4 <-> 5 成一个分量，10 只有入边和自环
+    fn cycles() -> Vec<Edge<MyEmpty>> {
+        edges(&[
+            (0, 1), (1, 2), (2, 0), (2, 3), (1, 6),
+            (3, 8), (8, 9), (9, 3), (3, 4), (4, 5), (5, 4),
+            (6, 7), (7, 11), (11, 6), (9, 10), (10, 10),
+        ])
+    }
+
+    #[test]
+    fn scc0() {
+        let communicatoner = com_for_test(52, 53, 0);
+        check(cycles(), &communicatoner, vec![0, 0, 0, 3, 4, 4, 6]);
+    }
+
+    #[test]
+    fn scc1() {
+        let communicatoner = com_for_test(52, 53, 1);
+        check(vec![], &communicatoner, vec![6, 3, 3, 10, 6]);
+    }
+
+    fn check_subgraph(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi, component : Vec<Vid>) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+        // 删掉 8 后环 3 -> 8 -> 9 -> 3 断开，压缩后 9 10 11 变为 8 9 10，子图中的边需要保持原来的方向
+        let sub = graph.subgraph(|v| v != 8, |_, _, _| true, true, communication);
+        let result = scc(&sub.graph, communication);
+        assert_eq!(result.component, component);
+        assert_eq!(result.components, 6);
+    }
+
+    #[test]
+    fn scc_subgraph0() {
+        let communicatoner = com_for_test(62, 63, 0);
+        check_subgraph(cycles(), &communicatoner, vec![0, 0, 0, 3, 4, 4]);
+    }
+
+    #[test]
+    fn scc_subgraph1() {
+        let communicatoner = com_for_test(62, 63, 1);
+        check_subgraph(vec![], &communicatoner, vec![6, 6, 8, 9, 6]);
+    }
+
+    // 有向链 0 -> 1 -> 2 -> 3 -> 4 -> 5 对称化后当作无向图：只有一个强连通分量，从 5 出发可以逆着链走到所有点
+    fn check_symmetrize(list : Vec<(Vid, Vid, f32)>, communication : &impl MyMpi, component : Vec<Vid>, dist : Vec<f32>, pred : Vec<Vid>) {
+        let graph = NearGraph::<f32, SeqSPartition>::new(weighted_edges(&list), communication);
+        assert_eq!(scc(&graph, communication).components, 6);
+
+        let mut option = CleanOption::default();
+        option.symmetrize = true;
+        option.merge = Some(combine::min::<f32>);
+        let graph = NearGraph::<f32, SeqSPartition>::new_with_option(weighted_edges(&list), communication, &option);
+        let result = scc(&graph, communication);
+        assert_eq!(result.component, component);
+        assert_eq!(result.components, 1);
+
+        let result = crate::algo::sssp::bellman_ford(&graph, 5, communication);
+        assert_eq!(result.dist, dist);
+        assert_eq!(result.pred, pred);
+    }
+
+    #[test]
+    fn scc_symmetrize0() {
+        let communicatoner = com_for_test(66, 67, 0);
+        let list = vec![(0, 1, 1.0), (1, 2, 1.0), (2, 3, 1.0), (3, 4, 1.0), (4, 5, 1.0)];
+        check_symmetrize(list, &communicatoner, vec![0, 0, 0, 0], vec![5.0, 4.0, 3.0, 2.0], vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn scc_symmetrize1() {
+        let communicatoner = com_for_test(66, 67, 1);
+        check_symmetrize(vec![], &communicatoner, vec![0, 0], vec![1.0, 0.0], vec![5, 5]);
+    }
+}
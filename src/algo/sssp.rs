@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+
+use bincode::{Encode, Decode};
+use rayon::iter::IntoParallelIterator;
+
+use crate::{graph::{Graph, SeqPartition, NearGraph}, common::base_structure::{Vid, Edge}, parallel::server::MyMpi, traits::Weight};
+
+use super::{exchange, assert_edge_cut};
+
+use std::fmt::Debug;
+
+#[derive(Debug)]
+pub struct SsspResult {
+    /// 本 rank 拥有的顶点到源点的距离，不可达为 f32::INFINITY
+    pub dist : Vec<f32>,
+
+    /// 最短路径上的前驱，源点的前驱是自己，不可达为 Vid::MAX
+    pub pred : Vec<Vid>,
+
+    /// 全局的松弛轮数
+    pub rounds : usize,
+
+    /// 是否存在从源点可达的负环，为 true 时 dist 和 pred 没有意义
+    pub negative_cycle : bool,
+}
+
+/// 用 Weight 作为边权的单源最短路，按边原来的方向只沿出边走，要求本 rank 拥有的顶点的邻接表完整
+struct Sssp<'a, EDATA, PART>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    graph : &'a NearGraph<EDATA, PART>,
+    start_id : Vid,
+    dist : Vec<f32>,
+    pred : Vec<Vid>,
+}
+
+impl<'a, EDATA, PART> Sssp<'a, EDATA, PART>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    fn new(graph : &'a NearGraph<EDATA, PART>, source : Vid) -> Self {
+        let partition = graph.partition();
+        let start_id = partition.start_id();
+        let len = (partition.end_id() - start_id) as usize;
+        let mut sssp = Sssp {
+            graph : graph,
+            start_id : start_id,
+            dist : vec![f32::INFINITY; len],
+            pred : vec![Vid::MAX; len],
+        };
+        if sssp.owns(source) {
+            sssp.dist[(source - start_id) as usize] = 0.0;
+            sssp.pred[(source - start_id) as usize] = source;
+        }
+        sssp
+    }
+
+    fn owns(&self, v : Vid) -> bool {
+        v >= self.start_id && ((v - self.start_id) as usize) < self.dist.len()
+    }
+
+    fn dist(&self, v : Vid) -> f32 {
+        self.dist[(v - self.start_id) as usize]
+    }
+
+    /// 松弛 vertices 中满足 keep(w) 的出边，把 (邻居, 新距离, 前驱) 发给邻居的 owner，返回距离变小的本地顶点
+    fn relax(&mut self, vertices : &[Vid], keep : impl Fn(f32) -> bool, communication : &impl MyMpi) -> Vec<Vid> {
+        let partition = self.graph.partition();
+        let mut msgs = vec![vec![]; communication.partitions()];
+        for &v in vertices {
+            let d = self.dist(v);
+            self.graph.for_each_weighted_out_nbr(v as usize, |to, w| {
+                if keep(w) {
+                    msgs[partition.vertex_partition(&to)].push((to, d + w, v));
+                }
+            });
+        }
+
+        let mut changed = vec![];
+        for (to, d, from) in exchange(msgs, communication) {
+            let index = (to - self.start_id) as usize;
+            if d < self.dist[index] {
+                self.dist[index] = d;
+                self.pred[index] = from;
+                changed.push(to);
+            }
+        }
+        changed.sort_unstable();
+        changed.dedup();
+        changed
+    }
+
+    fn result(self, rounds : usize, negative_cycle : bool) -> SsspResult {
+        SsspResult {
+            dist : self.dist,
+            pred : self.pred,
+            rounds : rounds,
+            negative_cycle : negative_cycle,
+        }
+    }
+}
+
+/// Bellman-Ford 风格的 SSSP：每轮只从上一轮距离变小的顶点出发松弛，只交换被松弛的顶点。
+/// 允许负权边；超过 vertex_num - 1 轮仍有顶点被更新时认为存在负环。
+pub fn bellman_ford<EDATA, PART>(graph : &NearGraph<EDATA, PART>, source : Vid, communication : &impl MyMpi) -> SsspResult
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    assert_edge_cut(graph.partition(), "bellman_ford");
+    let mut sssp = Sssp::new(graph, source);
+    let vertex_num = graph.graph_info.vertex_num as usize;
+
+    let mut active : Vec<Vid> = if sssp.owns(source) { vec![source] } else { vec![] };
+    let mut rounds = 0;
+    loop {
+        let active_num = communication.reduce(active.len(), |a, b| a + b);
+        if active_num == 0 {
+            return sssp.result(rounds, false);
+        }
+        if rounds >= vertex_num {
+            println!("bellman-ford: negative cycle detected");
+            return sssp.result(rounds, true);
+        }
+        rounds += 1;
+        active = sssp.relax(&active, |_| true, communication);
+        println!("bellman-ford round {rounds}: {active_num} active");
+    }
+}
+
+/// delta-stepping SSSP，边权必须非负。按距离把顶点放进宽度为 delta 的桶里，
+/// 所有 rank 一起按编号从小到大处理桶：先反复松弛桶内顶点的轻边（w <= delta）直到桶不再变化，再松弛一次重边
+pub fn delta_stepping<EDATA, PART>(graph : &NearGraph<EDATA, PART>, source : Vid, delta : f32, communication : &impl MyMpi) -> SsspResult
+where
+    PART : SeqPartition + Sync,
+    EDATA : Weight + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    assert_edge_cut(graph.partition(), "delta_stepping");
+    assert!(delta > 0.0, "delta must be positive");
+    let mut sssp = Sssp::new(graph, source);
+    let start_id = sssp.start_id;
+    let bucket = |d : f32| (d / delta) as u64;
+
+    // 桶里可能有过期的顶点（距离又变小了，或者已经处理完），取出时再检查
+    let mut buckets : BTreeMap<u64, Vec<Vid>> = BTreeMap::new();
+    let mut done = vec![false; sssp.dist.len()];
+    if sssp.owns(source) {
+        buckets.insert(0, vec![source]);
+    }
+    let valid = |v : Vid, key : u64, dist : &Vec<f32>, done : &Vec<bool>| {
+        let index = (v - start_id) as usize;
+        !done[index] && bucket(dist[index]) == key
+    };
+
+    let mut rounds = 0;
+    loop {
+        // 本地第一个非空的桶
+        let mut local_min = u64::MAX;
+        while let Some((&key, vertices)) = buckets.iter_mut().next() {
+            vertices.retain(|&v| valid(v, key, &sssp.dist, &done));
+            if vertices.is_empty() {
+                buckets.remove(&key);
+            }else {
+                local_min = key;
+                break;
+            }
+        }
+        let current = communication.reduce(local_min, |a, b| a.min(b));
+        if current == u64::MAX {
+            return sssp.result(rounds, false);
+        }
+
+        let mut settled = vec![];
+        loop {
+            let mut frontier = buckets.remove(&current).unwrap_or_default();
+            frontier.retain(|&v| valid(v, current, &sssp.dist, &done));
+            frontier.sort_unstable();
+            frontier.dedup();
+            let frontier_num = communication.reduce(frontier.len(), |a, b| a + b);
+            if frontier_num == 0 {
+                break;
+            }
+            rounds += 1;
+
+            for v in sssp.relax(&frontier, |w| {
+                assert!(w >= 0.0, "delta-stepping requires non-negative weights");
+                w <= delta
+            }, communication) {
+                buckets.entry(bucket(sssp.dist(v))).or_default().push(v);
+            }
+            settled.extend(frontier);
+        }
+
+        settled.sort_unstable();
+        settled.dedup();
+        rounds += 1;
+        for v in sssp.relax(&settled, |w| w > delta, communication) {
+            buckets.entry(bucket(sssp.dist(v))).or_default().push(v);
+        }
+        for v in settled {
+            done[(v - start_id) as usize] = true;
+        }
+        println!("delta-stepping bucket {current} finished, rounds: {rounds}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::weighted_edges, graph::SeqSPartition};
+
+    fn check(list : Vec<(Vid, Vid, f32)>, negative : Vec<(Vid, Vid, f32)>, cycle : Vec<(Vid, Vid, f32)>, communication : &impl MyMpi, dist : Vec<f32>, pred : Vec<Vid>, negative_dist : Vec<f32>) {
+        let graph = NearGraph::<f32, SeqSPartition>::new(weighted_edges(&list), communication);
+
+        let result = bellman_ford(&graph, 0, communication);
+        assert!(!result.negative_cycle);
+        assert_eq!(result.dist, dist);
+        assert_eq!(result.pred, pred);
+
+        for delta in [0.5, 2.0, 100.0] {
+            let result = delta_stepping(&graph, 0, delta, communication);
+            assert_eq!(result.dist, dist, "delta: {delta}");
+            assert_eq!(result.pred, pred, "delta: {delta}");
+        }
+
+        let graph = NearGraph::<f32, SeqSPartition>::new(weighted_edges(&negative), communication);
+        let result = bellman_ford(&graph, 0, communication);
+        assert!(!result.negative_cycle);
+        assert_eq!(result.dist, negative_dist);
+
+        let graph = NearGraph::<f32, SeqSPartition>::new(weighted_edges(&cycle), communication);
+        let result = bellman_ford(&graph, 0, communication);
+        assert!(result.negative_cycle);
+    }
+
+    #[test]
+    fn sssp0() {
+        let communicatoner = com_for_test(48, 49, 0);
+        // 5 - 6 与源点不连通，6 -> 3 只能反向走，不会被松弛
+        let list = vec![(0, 1, 4.0), (0, 2, 1.0), (2, 1, 2.0), (1, 3, 1.0), (2, 3, 5.0), (3, 4, 3.0), (5, 6, 1.0), (6, 3, -10.0)];
+        // 1 -> 2 是负权边但不在环上，1 -> 2 -> 1 才是负环
+        let negative = vec![(0, 1, 2.0), (0, 2, 1.0), (1, 2, -2.0), (2, 3, 1.0)];
+        let cycle = vec![(0, 1, 1.0), (1, 2, -2.0), (2, 1, 1.0)];
+        check(list, negative, cycle, &communicatoner, vec![0.0, 3.0, 1.0, 4.0], vec![0, 2, 0, 1], vec![0.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn sssp1() {
+        let communicatoner = com_for_test(48, 49, 1);
+        check(vec![], vec![], vec![], &communicatoner, vec![7.0, f32::INFINITY, f32::INFINITY], vec![3, Vid::MAX, Vid::MAX], vec![1.0]);
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{graph::{Graph, Pratition, SeqPartition, intersect}, common::base_structure::Vid, parallel::server::MyMpi};
+
+use super::{exchange, fetch, assert_edge_cut};
+
+#[derive(Debug)]
+pub struct TriangleResult {
+    /// 全局的三角形个数
+    pub triangles : u64,
+
+    /// 本 rank 拥有的每个顶点所在的三角形个数
+    pub count : Vec<u64>,
+
+    /// 本 rank 拥有的每个顶点的局部聚类系数，度数小于 2 的顶点为 0
+    pub lcc : Vec<f64>,
+}
+
+/// 精确的三角形计数，按无向简单图处理：忽略自环，重复边只算一次，要求本 rank 拥有的顶点的邻接表完整。
+/// 每条边按 (度数, id) 从小指向大定向，每个三角形只在定向后入度为 0 的那个顶点上被找到一次；
+/// 求交需要的远端顶点的定向邻接表通过 send_recv 向 owner 拉取，本地的求交用 rayon 并行
+pub fn triangle_count<G>(graph : &G, communication : &impl MyMpi) -> TriangleResult
+where
+    G : Graph + Sync,
+{
+    assert_edge_cut(graph.partition(), "triangle_count");
+    let partition = graph.partition();
+    let start_id = partition.start_id();
+    let end_id = partition.end_id();
+    let owned = |v : Vid| v >= start_id && v < end_id;
+
+    let nbrs : Vec<Vec<Vid>> = (start_id..end_id).into_par_iter().map(|v| {
+        let mut nbr = vec![];
+        graph.for_each_nbr(v as usize, |w| if w != v { nbr.push(w) });
+        nbr.sort_unstable();
+        nbr.dedup();
+        nbr
+    }).collect();
+    let nbr = |v : Vid| &nbrs[(v - start_id) as usize];
+
+    let mut remote : Vec<Vid> = nbrs.iter().flatten().cloned().filter(|&w| !owned(w)).collect();
+    remote.sort_unstable();
+    remote.dedup();
+    let remote_degree = fetch(graph, remote, |v| nbr(v).len(), communication);
+    let degree = |v : Vid| if owned(v) { nbr(v).len() } else { remote_degree[&v] };
+    let higher = |v : Vid, w : Vid| (degree(w), w) > (degree(v), v);
+
+    let oriented : Vec<Vec<Vid>> = (start_id..end_id).into_par_iter().map(|v| {
+        nbr(v).iter().cloned().filter(|&w| higher(v, w)).collect()
+    }).collect();
+    let out = |v : Vid| &oriented[(v - start_id) as usize];
+
+    let mut needed : Vec<Vid> = oriented.iter().flatten().cloned().filter(|&w| !owned(w)).collect();
+    needed.sort_unstable();
+    needed.dedup();
+    let remote_out = fetch(graph, needed, |v| out(v).clone(), communication);
+    let out_of = |v : Vid| if owned(v) { out(v) } else { &remote_out[&v] };
+
+    // 每个三角形 (v, u, w) 三个顶点各加一，本地顶点直接计数，远端顶点先在本地聚合，每个顶点只发一条消息
+    let (mut count, remote_count, found) = (start_id..end_id).into_par_iter().fold(
+        || (vec![0u64; (end_id - start_id) as usize], HashMap::<Vid, u64>::new(), 0u64),
+        |(mut count, mut remote_count, mut found), v| {
+            for &u in out(v).iter() {
+                intersect::intersect(out(v), out_of(u), |w| {
+                    found += 1;
+                    for x in [v, u, w] {
+                        if owned(x) {
+                            count[(x - start_id) as usize] += 1;
+                        }else {
+                            *remote_count.entry(x).or_default() += 1;
+                        }
+                    }
+                });
+            }
+            (count, remote_count, found)
+        },
+    ).reduce(
+        || (vec![0u64; (end_id - start_id) as usize], HashMap::new(), 0u64),
+        |(mut count, mut remote_count, found), (count1, remote_count1, found1)| {
+            count.iter_mut().zip(count1).for_each(|(a, b)| *a += b);
+            remote_count1.into_iter().for_each(|(x, c)| *remote_count.entry(x).or_default() += c);
+            (count, remote_count, found + found1)
+        },
+    );
+    let triangles = communication.reduce(found, |a, b| a + b);
+
+    let mut msgs = vec![vec![]; communication.partitions()];
+    for (x, c) in remote_count {
+        msgs[partition.vertex_partition(&x)].push((x, c));
+    }
+    for (x, c) in exchange(msgs, communication) {
+        count[(x - start_id) as usize] += c;
+    }
+
+    let lcc = count.iter().zip(nbrs.iter()).map(|(&t, nbr)| {
+        let d = nbr.len() as f64;
+        if nbr.len() < 2 { 0.0 } else { 2.0 * t as f64 / (d * (d - 1.0)) }
+    }).collect();
+    println!("triangle count: {triangles}");
+
+    TriangleResult {
+        triangles : triangles,
+        count : count,
+        lcc : lcc,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::{NearGraph, SeqSPartition}, common::base_structure::Edge};
+
+    fn check(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi, count : Vec<u64>, lcc : Vec<f64>) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+        let result = triangle_count(&graph, communication);
+        assert_eq!(result.triangles, 5);
+        assert_eq!(result.count, count);
+        assert_eq!(result.lcc, lcc);
+    }
+
+    #[test]
+    fn triangle0() {
+        let communicatoner = com_for_test(54, 55, 0);
+        // 0 1 2 3 组成 K4（4 个三角形），三角形 3 - 5 - 6 跨 rank，1 - 0 重复、4 自环、6 - 7 不在三角形里
+        let edges = edges(&[(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3), (1, 0), (3, 5), (5, 6), (6, 3), (4, 4), (6, 7)]);
+        check(edges, &communicatoner, vec![3, 3, 3, 4, 0], vec![1.0, 1.0, 1.0, 0.4, 0.0]);
+    }
+
+    #[test]
+    fn triangle1() {
+        let communicatoner = com_for_test(54, 55, 1);
+        check(vec![], &communicatoner, vec![1, 1, 0], vec![1.0, 1.0 / 3.0, 0.0]);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use crate::{graph::{Graph, Pratition, SeqPartition}, common::base_structure::Vid, parallel::server::MyMpi};
+
+use super::{exchange, gather_sizes, assert_edge_cut};
+
+#[derive(Debug)]
+pub struct WccResult {
+    /// 本 rank 拥有的顶点所在连通分量的标签，即分量中最小的顶点 id
+    pub label : Vec<Vid>,
+
+    /// 全局的连通分量数，包括孤立顶点
+    pub components : Vid,
+
+    /// (标签, 分量大小)，按标签升序，只在 rank 0 上为 Some
+    pub sizes : Option<Vec<(Vid, Vid)>>,
+}
+
+/// 本地的并查集，按较小的 id 合并，根就是集合中最小的顶点
+struct UnionFind {
+    parent : Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len : usize) -> Self {
+        UnionFind { parent : (0..len).collect() }
+    }
+
+    fn find(&mut self, mut x : usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a : usize, b : usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a < b {
+            self.parent[b] = a;
+        }else if b < a {
+            self.parent[a] = b;
+        }
+    }
+}
+
+/// 弱连通分量，要求本 rank 拥有的顶点的邻接表完整。
+/// 先在本地用并查集合并两端都属于本 rank 的边，得到的每个本地集合共用一个标签；
+/// 之后全局按最小标签传播，每轮只有标签变小的集合通过跨 rank 的边把新标签发给对端的 owner，
+/// 直到所有 rank 上都没有标签变化
+pub fn wcc<G>(graph : &G, communication : &impl MyMpi) -> WccResult
+where
+    G : Graph + Sync,
+{
+    assert_edge_cut(graph.partition(), "wcc");
+    let partitions = communication.partitions();
+    let partition = graph.partition();
+    let start_id = partition.start_id();
+    let end_id = partition.end_id();
+    let len = (end_id - start_id) as usize;
+    let owned = |v : Vid| v >= start_id && v < end_id;
+
+    let mut uf = UnionFind::new(len);
+    // 每个本地集合（以根的下标表示）跨 rank 的边 (本地顶点, 远端顶点)
+    let mut boundary : Vec<(usize, Vid)> = vec![];
+    for v in start_id..end_id {
+        let index = (v - start_id) as usize;
+        graph.for_each_nbr(v as usize, |w| {
+            if owned(w) {
+                uf.union(index, (w - start_id) as usize);
+            }else {
+                boundary.push((index, w));
+            }
+        });
+    }
+    let roots : Vec<usize> = (0..len).map(|i| uf.find(i)).collect();
+    let mut remote : BTreeMap<usize, Vec<Vid>> = BTreeMap::new();
+    for (index, w) in boundary {
+        remote.entry(roots[index]).or_default().push(w);
+    }
+    remote.values_mut().for_each(|nbrs| {
+        nbrs.sort_unstable();
+        nbrs.dedup();
+    });
+
+    // 只有根上的标签有意义
+    let mut root_label : Vec<Vid> = (start_id..end_id).collect();
+    let mut changed : Vec<usize> = remote.keys().cloned().collect();
+    let mut rounds = 0;
+    loop {
+        let changed_num = communication.reduce(changed.len(), |a, b| a + b);
+        if changed_num == 0 {
+            break;
+        }
+        rounds += 1;
+        println!("wcc round {rounds}: {changed_num} changed");
+
+        let mut msgs = vec![vec![]; partitions];
+        for root in changed {
+            for &w in remote[&root].iter() {
+                msgs[partition.vertex_partition(&w)].push((w, root_label[root]));
+            }
+        }
+
+        changed = vec![];
+        for (v, label) in exchange(msgs, communication) {
+            let root = roots[(v - start_id) as usize];
+            if label < root_label[root] {
+                root_label[root] = label;
+                changed.push(root);
+            }
+        }
+        changed.sort_unstable();
+        changed.dedup();
+    }
+
+    let label : Vec<Vid> = roots.iter().map(|&root| root_label[root]).collect();
+    let local_components = label.iter().enumerate().filter(|&(i, &l)| l == start_id + i as Vid).count() as Vid;
+    let components = communication.reduce(local_components, |a, b| a + b);
+    let sizes = gather_sizes(&label, communication);
+
+    WccResult {
+        label : label,
+        components : components,
+        sizes : sizes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::{NearGraph, SeqSPartition}, common::base_structure::Edge};
+
+    fn check(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi, label : Vec<Vid>) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+        let result = wcc(&graph, communication);
+        assert_eq!(result.label, label);
+        assert_eq!(result.components, 4);
+        if communication.get_cluster_info().rank == 0 {
+            assert_eq!(result.sizes.unwrap(), vec![(0, 7), (3, 2), (5, 1), (9, 2)]);
+        }else {
+            assert!(result.sizes.is_none());
+        }
+    }
+
+    #[test]
+    fn wcc0() {
+        let communicatoner = com_for_test(50, 51, 0);
+        // 0 - 1 - 2 - 7 - 8 - 10 - 6 在两个 rank 之间来回跨越，3 - 4 和 9 - 11 各自成分量，5 孤立
+        let edges = edges(&[(0, 1), (1, 2), (2, 7), (7, 8), (8, 10), (10, 6), (3, 4), (9, 11)]);
+        check(edges, &communicatoner, vec![0, 0, 0, 3, 3, 5, 0]);
+    }
+
+    #[test]
+    fn wcc1() {
+        let communicatoner = com_for_test(50, 51, 1);
+        check(vec![], &communicatoner, vec![0, 0, 9, 0, 9]);
+    }
+}
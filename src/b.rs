@@ -6,14 +6,21 @@ use rayon::{iter::IntoParallelRefIterator, slice::ParallelSliceMut};
 fn main() {
     let communicatoner: SyncCommunicationer = com_for_test(5, 6, 0);
     
-    // read from rank 0
-    let a = CsvReader::new();
-    let mut read = ReadOption::default();
-    read.header = "from:uint,to:uint".into();
-    read.has_header = false;
-    let edges = a.read_edge::<MyEmpty>("data/1000w.csv".into(), read);
+    let graph = match NearGraph::<MyEmpty, SeqSPartition>::load_snapshot("data/1000w.snapshot".into(), &communicatoner) {
+        Some(graph) => graph,
+        None => {
+            // read from rank 0
+            let a = CsvReader::new();
+            let mut read = ReadOption::default();
+            read.header = "from:uint,to:uint".into();
+            read.has_header = false;
+            let edges = a.read_edge::<MyEmpty>("data/1000w.csv".into(), read);
 
-    let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, &communicatoner);
+            let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, &communicatoner);
+            graph.save_snapshot("data/1000w.snapshot".into(), &communicatoner);
+            graph
+        }
+    };
 
     let t0 = Instant::now();
     let pool = rayon::ThreadPoolBuilder::new().num_threads(3).build().unwrap();
@@ -23,6 +23,14 @@ impl Serilazer {
         let (decoded, _): (T, usize) = bincode::decode_from_slice(&val[..], self.config).unwrap();
         decoded
     }
+
+    /// 和 decode 相同，字节无法解码时返回 None，用于读取可能损坏或版本不符的文件
+    pub fn try_decode<T>(&self, val : &[u8]) -> Option<T>
+    where
+        T : bincode::Decode
+    {
+        bincode::decode_from_slice(val, self.config).ok().map(|(decoded, _)| decoded)
+    }
 }
 
 #[cfg(test)]
@@ -1,15 +1,32 @@
 use bincode::{Encode, Decode};
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator, ParallelDrainFull, IntoParallelIterator, IndexedParallelIterator, ParallelExtend};
+use rayon::iter::{IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator, ParallelDrainFull, IntoParallelIterator, IndexedParallelIterator, ParallelExtend};
 
 use crate::common::base_structure::{Vid, Edge, Eid};
 use crate::parallel::server::MyMpi;
+use crate::traits::Weight;
 
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use std::fmt::Debug;
 
-#[derive(Debug)]
+pub mod vertex_cut;
+pub mod report;
+pub mod stats;
+pub mod clean;
+pub mod intersect;
+pub mod snapshot;
+pub mod mmap;
+pub mod update;
+pub mod subgraph;
+pub mod khop;
+pub mod traversal;
+pub mod property;
+
+use clean::CleanOption;
+use property::PropertyStore;
+
+#[derive(Debug, Encode, Decode)]
 pub struct GraphInfo {
     pub vertex_num : Vid,
     pub edge_num : Eid,
@@ -65,6 +82,20 @@ pub trait SeqPartition : Pratition {
 
     fn start_id(&self) -> Vid;
     fn end_id(&self) -> Vid;
+
+    /// 是否为 vertex-cut 划分：每条边只存在一个 rank 上，顶点可能在多个 rank 上有副本
+    fn vertex_cut(&self) -> bool {
+        false
+    }
+
+    /// 本 rank 是否给边 (from, to) 计数。edge-cut 下被切断的边在两个 rank 上各有一份，
+    /// 只在较小端点所在的 rank 上计数；vertex-cut 下每条边只有一份，总是计数
+    fn counts_edge(&self, from : Vid, to : Vid, rank : usize) -> bool {
+        self.vertex_cut() || self.vertex_partition(&from.min(to)) == rank
+    }
+
+    /// 顶点数增长到 vertex_num，新出现的顶点归最后一个 rank 所有
+    fn grow(&mut self, vertex_num : Vid);
 }
 
 #[derive(Debug)]
@@ -88,7 +119,7 @@ impl Pratition for HashPartition {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Encode, Decode)]
 pub struct SeqSPartition {
     rank : usize,
     end_id : Vec<Vid>
@@ -109,8 +140,9 @@ impl SeqPartition for SeqSPartition {
         let v = graph_info.vertex_num;
         let mut end_id = vec![];
         let chunk = v / cluster_info.partitions as u32 + 1;
+        // 顶点比 rank 少时后面的 rank 分到空区间，不能让 start_id 超过 end_id
         for i in 1..cluster_info.partitions as u32 {
-            end_id.push(chunk * i);
+            end_id.push((chunk * i).min(v));
         }
         end_id.push(v);
         SeqSPartition { rank : cluster_info.rank, end_id: end_id }
@@ -175,13 +207,18 @@ impl SeqPartition for SeqSPartition {
     fn end_id(&self) -> Vid {
         self.end_id[self.rank]
     }
+
+    fn grow(&mut self, vertex_num : Vid) {
+        let last = self.end_id.last_mut().unwrap();
+        *last = vertex_num.max(*last);
+    }
 }
 
 pub trait Graph {
     type PART : SeqPartition;
     fn local_vertexs(&self) -> Vid;
 
-    // 获取一个数组。
+    // 获取一个数组，长度为本 rank 拥有的顶点数。
     fn get_array<T>(&self, init_data : T) -> Vec<T>
     where
         T : Clone;
@@ -191,12 +228,50 @@ pub trait Graph {
 
     /// 返回这玩意
     fn partition(&self) -> &Self::PART;
+
+    /// 依次访问顶点 id 的每个邻居
+    fn for_each_nbr(&self, id : usize, f : impl FnMut(Vid));
+
+    /// 依次访问顶点 id 的邻接表中下标在 [lo, hi) 内的邻居，顺序与 for_each_nbr 一致
+    fn for_each_nbr_range(&self, id : usize, lo : usize, hi : usize, f : impl FnMut(Vid));
+
+    /// 按 for_each_nbr 的顺序返回顶点 id 第一个满足 pred 的邻居，找到后不再访问剩下的邻居
+    fn find_nbr(&self, id : usize, pred : impl FnMut(Vid) -> bool) -> Option<Vid>;
+
+    /// 是否存在边 (u, v)，u 的邻接表需要在本地完整
+    fn has_edge(&self, u : Vid, v : Vid) -> bool;
+
+    /// u 和 v 的公共邻居，邻接表需要已排序且在本地完整
+    fn common_nbrs(&self, u : Vid, v : Vid) -> Vec<Vid>;
+
+    /// u 和 v 的公共邻居个数，邻接表需要已排序且在本地完整
+    fn common_nbrs_count(&self, u : Vid, v : Vid) -> usize;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Encode, Decode)]
 pub struct NearEdge<EDATA> {
     pub to : Vid,
     data : EDATA,
+
+    /// 原始边的方向：为 true 时是本顶点指向 to 的出边，为 false 时是 to 指向本顶点的入边
+    out : bool,
+}
+
+impl<EDATA> NearEdge<EDATA> {
+    pub fn data(&self) -> &EDATA {
+        &self.data
+    }
+
+    pub fn is_out(&self) -> bool {
+        self.out
+    }
+
+    pub fn weight(&self) -> f32
+    where
+        EDATA : Weight,
+    {
+        self.data.weight()
+    }
 }
 
 #[derive(Debug)]
@@ -209,6 +284,11 @@ where
     pub graph_info : GraphInfo,
     pub g : Vec<Vec<NearEdge<EDATA>>>,
     partition : PART,
+
+    /// 每个邻接表是否按 to 升序
+    sorted : bool,
+    /// 本 rank 拥有的顶点上的属性列
+    properties : PropertyStore,
 }
 
 impl<EDATA, PART> NearGraph<EDATA, PART> 
@@ -221,20 +301,104 @@ where
         &self.g[id]
     }
 
+    /// 把每个邻接表按 to 升序排序，平行边保持原来的相对顺序
+    pub fn sort_nbr(&mut self) {
+        self.g.par_iter_mut().for_each(|nbr| nbr.sort_by_key(|edge| edge.to));
+        self.sorted = true;
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// 依次访问顶点 id 的每个邻居和对应的边权
+    pub fn for_each_weighted_nbr(&self, id : usize, mut f : impl FnMut(Vid, f32))
+    where
+        EDATA : Weight,
+    {
+        self.g[id].iter().for_each(|edge| f(edge.to, edge.data.weight()));
+    }
+
+    /// 依次访问顶点 id 的出边邻居，自环访问一次
+    pub fn for_each_out_nbr(&self, id : usize, mut f : impl FnMut(Vid)) {
+        self.g[id].iter().filter(|edge| edge.out).for_each(|edge| f(edge.to));
+    }
+
+    /// 依次访问顶点 id 的出边邻居和边权，自环访问一次
+    pub fn for_each_weighted_out_nbr(&self, id : usize, mut f : impl FnMut(Vid, f32))
+    where
+        EDATA : Weight,
+    {
+        self.g[id].iter().filter(|edge| edge.out).for_each(|edge| f(edge.to, edge.data.weight()));
+    }
+
+    /// 依次访问顶点 id 的入边邻居，自环访问一次
+    pub fn for_each_in_nbr(&self, id : usize, mut f : impl FnMut(Vid)) {
+        self.g[id].iter().filter(|edge| !edge.out).for_each(|edge| f(edge.to));
+    }
+
+    /// 依次访问顶点 u 的邻接表中由本 rank 取出的边，按原来的方向给出 (from, to, data)。
+    /// 每条边在两个端点的邻接表里各存一份，只从较小的端点取出，自环只取出边的那一份；
+    /// 非 vertex-cut 划分只由 u 的 owner 取出，vertex-cut 划分下每条边只存在一个 rank 上，每个 rank 都取。
+    /// 所有 rank 对所有顶点调用后，每条边恰好被取出一次
+    pub(crate) fn for_each_unique_edge(&self, u : Vid, mut f : impl FnMut(Vid, Vid, &EDATA)) {
+        if !self.partition.vertex_cut() && (u < self.partition.start_id() || u >= self.partition.end_id()) {
+            return;
+        }
+        for edge in self.g[u as usize].iter() {
+            if edge.to < u || (edge.to == u && !edge.out) {
+                continue;
+            }
+            if edge.out {
+                f(u, edge.to, &edge.data);
+            }else {
+                f(edge.to, u, &edge.data);
+            }
+        }
+    }
+
+    pub fn properties(&self) -> &PropertyStore {
+        &self.properties
+    }
+
+    pub fn properties_mut(&mut self) -> &mut PropertyStore {
+        &mut self.properties
+    }
+
     pub fn new(edges : Vec<Edge<EDATA>>, communication : &impl MyMpi) -> Self 
+    {
+        NearGraph::new_with_option(edges, communication, &CleanOption::default())
+    }
+
+    /// 在 impl_partition 之后按 option 清洗边再建图，edge_num 为清洗后的边数
+    pub fn new_with_option(edges : Vec<Edge<EDATA>>, communication : &impl MyMpi, option : &CleanOption<EDATA>) -> Self 
     {
         let graph_info = GraphInfo::from(&edges, communication);
+        NearGraph::build(graph_info, edges, communication, option)
+    }
+
+    /// 按给定的 graph_info 建图，vertex_num 可以大于边里出现的最大 id
+    pub(crate) fn build(mut graph_info : GraphInfo, edges : Vec<Edge<EDATA>>, communication : &impl MyMpi, option : &CleanOption<EDATA>) -> Self 
+    {
         let cluster_info = communication.get_cluster_info();
         let partition = PART::new(vec![], &graph_info, cluster_info);
         let edges = partition.impl_partition(edges, communication);
+        let edges = if option.is_noop() {
+            edges
+        }else {
+            let edges = option.clean(edges);
+            let local_edge = edges.par_iter().filter(|edge| partition.counts_edge(edge.from, edge.to, cluster_info.rank)).count() as Eid;
+            graph_info.edge_num = communication.reduce(local_edge, |a, b| a + b);
+            edges
+        };
         // println!("{:?}", edges);
         let global_vertexs = graph_info.vertex_num as usize;
         println!("builg g");
         
         let mut g: Vec<Vec<_>> = vec![vec![]; global_vertexs];
         edges.into_iter().for_each(|b| {
-            g[b.from as usize].push(NearEdge{to : b.to, data : b.data.clone() });
-            g[b.to as usize].push(NearEdge{to : b.from, data : b.data });
+            g[b.from as usize].push(NearEdge{to : b.to, data : b.data.clone(), out : true });
+            g[b.to as usize].push(NearEdge{to : b.from, data : b.data, out : false });
         });
 
         // 这个并行方式并不行
@@ -265,11 +429,17 @@ where
         //                 .collect()
         //         }
         //     );
-        let build_result = NearGraph {
+        let properties = PropertyStore::new(partition.start_id(), partition.end_id());
+        let mut build_result = NearGraph {
             graph_info : graph_info,
             g : g,
-            partition : partition
+            partition : partition,
+            sorted : false,
+            properties : properties,
         };
+        if option.sort_nbr {
+            build_result.sort_nbr();
+        }
         println!("builg g over");
         // println!("{:?}", build_result);
         build_result
@@ -293,7 +463,7 @@ where
     where
         T : Clone
     {
-        vec![init_data; self.local_vertexs() as usize]
+        vec![init_data; (self.partition.end_id() - self.partition.start_id()) as usize]
     }
 
     fn local_vertexs(&self) -> Vid {
@@ -306,6 +476,41 @@ where
     {
         &self.partition
     }
+
+    fn for_each_nbr(&self, id : usize, mut f : impl FnMut(Vid)) {
+        self.g[id].iter().for_each(|edge| f(edge.to));
+    }
+
+    fn for_each_nbr_range(&self, id : usize, lo : usize, hi : usize, mut f : impl FnMut(Vid)) {
+        self.g[id][lo..hi].iter().for_each(|edge| f(edge.to));
+    }
+
+    fn find_nbr(&self, id : usize, mut pred : impl FnMut(Vid) -> bool) -> Option<Vid> {
+        self.g[id].iter().map(|edge| edge.to).find(|&to| pred(to))
+    }
+
+    fn has_edge(&self, u : Vid, v : Vid) -> bool {
+        let nbr = &self.g[u as usize];
+        if self.sorted {
+            intersect::contains(nbr, v)
+        }else {
+            nbr.iter().any(|edge| edge.to == v)
+        }
+    }
+
+    fn common_nbrs(&self, u : Vid, v : Vid) -> Vec<Vid> {
+        assert!(self.sorted, "call sort_nbr before intersecting adjacency lists");
+        let mut res = vec![];
+        intersect::intersect(&self.g[u as usize], &self.g[v as usize], |x| res.push(x));
+        res
+    }
+
+    fn common_nbrs_count(&self, u : Vid, v : Vid) -> usize {
+        assert!(self.sorted, "call sort_nbr before intersecting adjacency lists");
+        let mut count = 0;
+        intersect::intersect(&self.g[u as usize], &self.g[v as usize], |_| count += 1);
+        count
+    }
 }
 
 #[cfg(test)]
@@ -344,4 +549,77 @@ mod tests {
         let graph = NearGraph::<MyEDATA, SeqSPartition>::new(edges, &communicatoner);
         println!("{:?}", graph);
     }
-}
\ No newline at end of file
+
+    fn dirty_edges() -> Vec<Edge<MyEmpty>> {
+        edges(&[(0, 1), (1, 0), (0, 1), (2, 2), (1, 3), (3, 1)])
+    }
+
+    fn clean_option() -> CleanOption<MyEmpty> {
+        let mut option = CleanOption::default();
+        option.drop_self_loops = true;
+        option.merge = Some(clean::combine::first);
+        option.merge_reverse = true;
+        option
+    }
+
+    #[test]
+    fn clean0() {
+        let communicatoner = com_for_test(18, 19, 0);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new_with_option(dirty_edges(), &communicatoner, &clean_option());
+        assert_eq!(graph.graph_info.edge_num, 2);
+        assert_eq!(graph.degrees(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn clean1() {
+        let communicatoner = com_for_test(18, 19, 1);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new_with_option(vec![], &communicatoner, &clean_option());
+        assert_eq!(graph.graph_info.edge_num, 2);
+        assert_eq!(graph.degrees(), vec![1]);
+    }
+
+    #[test]
+    fn seq_partition_small() {
+        let ranges = |vertex_num : Vid, partitions : usize| -> Vec<(Vid, Vid)> {
+            let graph_info = GraphInfo { vertex_num : vertex_num, edge_num : 0 };
+            (0..partitions).map(|rank| {
+                let partition = SeqSPartition::new(vec![], &graph_info, &ClusterInfo { partitions : partitions, rank : rank });
+                (partition.start_id(), partition.end_id())
+            }).collect()
+        };
+        assert_eq!(ranges(5, 4), vec![(0, 2), (2, 4), (4, 5), (5, 5)]);
+        assert_eq!(ranges(0, 2), vec![(0, 0), (0, 0)]);
+        assert_eq!(ranges(8, 2), vec![(0, 5), (5, 8)]);
+    }
+
+    fn sorted_option() -> CleanOption<MyEmpty> {
+        let mut option = CleanOption::default();
+        option.sort_nbr = true;
+        option
+    }
+
+    #[test]
+    fn sorted0() {
+        let communicatoner = com_for_test(20, 21, 0);
+        let edges = edges(&[(0, 3), (0, 1), (2, 1), (0, 2), (3, 1)]);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new_with_option(edges, &communicatoner, &sorted_option());
+        assert!(graph.is_sorted());
+        assert_eq!(graph.nbr(0).iter().map(|x| x.to).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(graph.has_edge(1, 3));
+        assert!(!graph.has_edge(1, 1));
+        assert_eq!(graph.common_nbrs(0, 1), vec![2, 3]);
+        assert_eq!(graph.common_nbrs_count(1, 2), 1);
+        let mut visited = 0;
+        assert_eq!(graph.find_nbr(0, |w| { visited += 1; w >= 2 }), Some(2));
+        assert_eq!(visited, 2);
+        assert_eq!(graph.find_nbr(0, |w| w > 3), None);
+    }
+
+    #[test]
+    fn sorted1() {
+        let communicatoner = com_for_test(20, 21, 1);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new_with_option(vec![], &communicatoner, &sorted_option());
+        assert!(graph.has_edge(3, 0));
+        assert_eq!(graph.nbr(3).iter().map(|x| x.to).collect::<Vec<_>>(), vec![0, 1]);
+    }
+}
@@ -0,0 +1,158 @@
+use rayon::slice::ParallelSliceMut;
+
+use crate::common::base_structure::{Vid, Edge};
+
+/// 合并平行边时常用的 EDATA 合并函数
+pub mod combine {
+    use std::ops::Add;
+
+    /// 保留先出现的边
+    pub fn first<T>(a : T, _b : T) -> T {
+        a
+    }
+
+    pub fn sum<T : Add<Output = T>>(a : T, b : T) -> T {
+        a + b
+    }
+
+    pub fn min<T : PartialOrd>(a : T, b : T) -> T {
+        if b < a { b } else { a }
+    }
+
+    pub fn max<T : PartialOrd>(a : T, b : T) -> T {
+        if b > a { b } else { a }
+    }
+}
+
+/// 建图前对每个 rank 收到的边做的预处理，在 impl_partition 之后执行
+pub struct CleanOption<EDATA> {
+    /// 去掉自环
+    pub drop_self_loops : bool,
+
+    /// 合并平行边使用的函数，例如 `combine::sum::<f32>`。None 表示保留平行边
+    pub merge : Option<fn(EDATA, EDATA) -> EDATA>,
+
+    /// 合并平行边时把 (u, v) 与 (v, u) 视为同一条无向边一起合并，保留先出现的边的方向。
+    /// 为 false 时 (u, v) 与 (v, u) 是两条不同的边。只在 merge 不为 None 时生效
+    pub merge_reverse : bool,
+
+    /// 把输入当作有向边做对称化：给每条非自环的边 (u, v) 补上数据相同的反向边 (v, u)，之后两个方向都可以走。
+    /// 在 merge 之前执行，输入里原有的反向边和补上的反向边会一起合并
+    pub symmetrize : bool,
+
+    /// 建图后把每个邻接表按 to 排序
+    pub sort_nbr : bool,
+}
+
+impl<EDATA> CleanOption<EDATA> {
+    pub fn default() -> Self {
+        CleanOption {
+            drop_self_loops : false,
+            merge : None,
+            merge_reverse : false,
+            symmetrize : false,
+            sort_nbr : false,
+        }
+    }
+
+    /// 是否不需要清洗边，merge 为 None 时 merge_reverse 不起作用
+    pub fn is_noop(&self) -> bool {
+        !self.drop_self_loops && !self.symmetrize && self.merge.is_none()
+    }
+
+    fn key(&self, edge : &Edge<EDATA>) -> (Vid, Vid) {
+        if self.merge_reverse {
+            (edge.from.min(edge.to), edge.from.max(edge.to))
+        }else {
+            (edge.from, edge.to)
+        }
+    }
+
+    pub fn clean(&self, mut edges : Vec<Edge<EDATA>>) -> Vec<Edge<EDATA>>
+    where
+        EDATA : Clone + Send,
+    {
+        if self.drop_self_loops {
+            edges.retain(|edge| edge.from != edge.to);
+        }
+
+        if self.symmetrize {
+            let reverse : Vec<Edge<EDATA>> = edges.iter().filter(|edge| edge.from != edge.to).map(|edge| {
+                Edge { from : edge.to, to : edge.from, data : edge.data.clone() }
+            }).collect();
+            edges.extend(reverse);
+        }
+
+        let merge = match self.merge {
+            Some(merge) => merge,
+            None => return edges,
+        };
+
+        // 稳定排序，保证 combine::first 保留的是输入中先出现的边
+        edges.par_sort_by_key(|edge| self.key(edge));
+
+        let mut result : Vec<Edge<EDATA>> = Vec::with_capacity(edges.len());
+        let mut iter = edges.into_iter();
+        let mut current = match iter.next() {
+            Some(edge) => edge,
+            None => return result,
+        };
+        for edge in iter {
+            if self.key(&current) == self.key(&edge) {
+                current.data = merge(current.data, edge.data);
+            }else {
+                result.push(std::mem::replace(&mut current, edge));
+            }
+        }
+        result.push(current);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::example::weighted_edges;
+
+    #[test]
+    fn drop_self_loops() {
+        let mut option = CleanOption::default();
+        option.drop_self_loops = true;
+        let result = option.clean(weighted_edges(&[(0, 0, 1.0), (0, 1, 2.0), (2, 2, 3.0)]));
+        assert_eq!(result, weighted_edges(&[(0, 1, 2.0)]));
+    }
+
+    #[test]
+    fn merge_parallel() {
+        let input = vec![(0, 1, 1.0), (1, 0, 2.0), (0, 1, 3.0), (1, 2, 4.0)];
+
+        let mut option = CleanOption::default();
+        option.merge = Some(combine::sum::<f32>);
+        assert_eq!(option.clean(weighted_edges(&input)), weighted_edges(&[(0, 1, 4.0), (1, 0, 2.0), (1, 2, 4.0)]));
+
+        option.merge = Some(combine::min::<f32>);
+        option.merge_reverse = true;
+        assert_eq!(option.clean(weighted_edges(&input)), weighted_edges(&[(0, 1, 1.0), (1, 2, 4.0)]));
+
+        option.merge = Some(combine::first::<f32>);
+        assert_eq!(option.clean(weighted_edges(&[(1, 0, 2.0), (0, 1, 1.0)])), weighted_edges(&[(1, 0, 2.0)]));
+
+        let mut option = CleanOption::<f32>::default();
+        option.merge_reverse = true;
+        assert!(option.is_noop());
+        assert_eq!(option.clean(weighted_edges(&input)), weighted_edges(&input));
+    }
+
+    #[test]
+    fn symmetrize() {
+        let mut option = CleanOption::default();
+        option.symmetrize = true;
+        assert!(!option.is_noop());
+        assert_eq!(option.clean(weighted_edges(&[(0, 1, 1.0), (2, 2, 2.0)])), weighted_edges(&[(0, 1, 1.0), (2, 2, 2.0), (1, 0, 1.0)]));
+
+        // 原有的反向边和补上的反向边一起合并，每个方向各保留一条
+        option.merge = Some(combine::sum::<f32>);
+        let result = option.clean(weighted_edges(&[(0, 1, 1.0), (1, 0, 2.0), (1, 2, 4.0)]));
+        assert_eq!(result, weighted_edges(&[(0, 1, 3.0), (1, 0, 3.0), (1, 2, 4.0), (2, 1, 4.0)]));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::common::base_structure::Vid;
+
+use super::NearEdge;
+
+/// 两个邻接表长度相差超过这个倍数时改用 galloping 求交
+const GALLOPING_RATIO : usize = 32;
+
+/// 可以参与求交的邻接表元素
+pub trait Target {
+    fn target(&self) -> Vid;
+}
+
+impl Target for Vid {
+    fn target(&self) -> Vid {
+        *self
+    }
+}
+
+impl<EDATA> Target for NearEdge<EDATA> {
+    fn target(&self) -> Vid {
+        self.to
+    }
+}
+
+/// 有序表上二分查找 vid
+pub fn contains<A : Target>(a : &[A], vid : Vid) -> bool {
+    a.binary_search_by_key(&vid, |x| x.target()).is_ok()
+}
+
+/// 归并求交，a、b 需要按 target 升序。重复元素按多重集合处理
+pub fn merge_intersect<A : Target, B : Target>(a : &[A], b : &[B], mut f : impl FnMut(Vid)) {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let (x, y) = (a[i].target(), b[j].target());
+        if x < y {
+            i += 1;
+        }else if x > y {
+            j += 1;
+        }else {
+            f(x);
+            i += 1;
+            j += 1;
+        }
+    }
+}
+
+/// galloping 求交，适合 small 远短于 large 的情况。a、b 需要按 target 升序
+pub fn galloping_intersect<A : Target, B : Target>(small : &[A], large : &[B], mut f : impl FnMut(Vid)) {
+    let mut begin = 0;
+    for x in small.iter().map(|x| x.target()) {
+        // 指数扩大步长找到包含 x 的区间，再在区间内二分
+        let mut step = 1;
+        let mut end = begin;
+        while end < large.len() && large[end].target() < x {
+            begin = end;
+            end += step;
+            step <<= 1;
+        }
+        let end = end.min(large.len());
+        begin += large[begin..end].partition_point(|y| y.target() < x);
+        if begin == large.len() {
+            return;
+        }
+        if large[begin].target() == x {
+            f(x);
+            begin += 1;
+        }
+    }
+}
+
+/// 根据长度自动选择归并或 galloping 求交
+pub fn intersect<A : Target, B : Target>(a : &[A], b : &[B], f : impl FnMut(Vid)) {
+    if a.len() * GALLOPING_RATIO < b.len() {
+        galloping_intersect(a, b, f)
+    }else if b.len() * GALLOPING_RATIO < a.len() {
+        galloping_intersect(b, a, f)
+    }else {
+        merge_intersect(a, b, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(f : impl Fn(&mut Vec<Vid>)) -> Vec<Vid> {
+        let mut res = vec![];
+        f(&mut res);
+        res
+    }
+
+    #[test]
+    fn intersect_sorted() {
+        let a : Vec<Vid> = vec![1, 3, 3, 5, 7, 9];
+        let b : Vec<Vid> = (0..200).collect();
+        let c : Vec<Vid> = vec![3, 3, 4, 9, 10];
+
+        assert_eq!(collect(|res| merge_intersect(&a, &c, |x| res.push(x))), vec![3, 3, 9]);
+        assert_eq!(collect(|res| galloping_intersect(&a, &b, |x| res.push(x))), vec![1, 3, 5, 7, 9]);
+        assert_eq!(collect(|res| galloping_intersect(&c, &a, |x| res.push(x))), vec![3, 3, 9]);
+        assert_eq!(collect(|res| intersect(&b, &a, |x| res.push(x))), vec![1, 3, 5, 7, 9]);
+        assert!(contains(&a, 7));
+        assert!(!contains(&a, 8));
+    }
+}
@@ -0,0 +1,139 @@
+use bincode::{Encode, Decode};
+use rayon::iter::IntoParallelIterator;
+
+use crate::common::base_structure::{Vid, Edge};
+use crate::parallel::server::MyMpi;
+
+use super::{SeqPartition, NearGraph};
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+/// k 跳邻域，收集到调用方指定的 rank 上
+#[derive(Debug)]
+pub struct KHop<EDATA> {
+    /// 邻域内的顶点，升序
+    pub vertices : Vec<Vid>,
+
+    /// 两个端点都在邻域内的边，保持原来的方向，按 (from, to) 升序
+    pub edges : Vec<Edge<EDATA>>,
+}
+
+impl<EDATA, PART> NearGraph<EDATA, PART>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync + Debug + 'static,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    /// 求 seeds 的 k 跳邻域（ego network），所有 rank 需要同时调用，每个 rank 可以给出不同的 seeds。
+    /// 逐层扩展，每层由所有 rank 扫描本地邻接表，新发现的顶点通过 send_recv 广播给所有 rank。
+    /// 顶点总数超过 max_vertices 时按 id 从小到大截断并停止扩展。
+    /// 结果只在 root 上返回 Some
+    pub fn k_hop(&self, seeds : Vec<Vid>, k : usize, max_vertices : usize, root : usize, communication : &impl MyMpi) -> Option<KHop<EDATA>> {
+        let rank = communication.get_cluster_info().rank;
+        let partitions = communication.partitions();
+        let all_gather = |local : Vec<Vid>| -> Vec<Vid> {
+            let msgs = vec![local; partitions];
+            let mut recv : Vec<Vid> = communication.send_recv::<Vec<Vid>>(msgs).into_iter().flatten().collect();
+            recv.sort_unstable();
+            recv.dedup();
+            recv
+        };
+
+        let mut frontier = all_gather(seeds);
+        frontier.retain(|&v| v < self.graph_info.vertex_num);
+        frontier.truncate(max_vertices);
+        let mut visited : HashSet<Vid> = frontier.iter().copied().collect();
+
+        for hop in 0..k {
+            if frontier.is_empty() || visited.len() >= max_vertices {
+                break;
+            }
+            let mut found = vec![];
+            for &v in frontier.iter() {
+                for edge in self.g[v as usize].iter() {
+                    if !visited.contains(&edge.to) {
+                        found.push(edge.to);
+                    }
+                }
+            }
+            found.sort_unstable();
+            found.dedup();
+
+            frontier = all_gather(found);
+            frontier.truncate(max_vertices - visited.len());
+            visited.extend(frontier.iter().copied());
+            println!("k-hop {}: {} vertices", hop + 1, visited.len());
+        }
+
+        let mut edges = vec![];
+        for &u in visited.iter() {
+            self.for_each_unique_edge(u, |from, to, data| {
+                if visited.contains(&from) && visited.contains(&to) {
+                    edges.push(Edge { from : from, to : to, data : data.clone() });
+                }
+            });
+        }
+
+        let recv = communication.gather(edges, root);
+        if rank != root {
+            return None;
+        }
+        let mut vertices : Vec<Vid> = visited.into_iter().collect();
+        vertices.sort_unstable();
+        let mut edges : Vec<Edge<EDATA>> = recv.into_iter().flatten().collect();
+        edges.sort_by_key(|edge| (edge.from, edge.to));
+        Some(KHop { vertices : vertices, edges : edges })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel::server::*;
+    use crate::graph::SeqSPartition;
+
+    fn pairs(edges : &Vec<Edge<f32>>) -> Vec<(Vid, Vid)> {
+        edges.iter().map(|edge| (edge.from, edge.to)).collect()
+    }
+
+    fn check(edges : Vec<Edge<f32>>, seeds : Vec<Vid>, communication : &impl MyMpi) {
+        let graph = NearGraph::<f32, SeqSPartition>::new(edges, communication);
+
+        let one = graph.k_hop(seeds.clone(), 1, 100, 0, communication);
+        let two = graph.k_hop(seeds.clone(), 2, 100, 0, communication);
+        let limited = graph.k_hop(seeds, 2, 3, 0, communication);
+        if communication.get_cluster_info().rank != 0 {
+            assert!(one.is_none() && two.is_none() && limited.is_none());
+            return;
+        }
+
+        let one = one.unwrap();
+        assert_eq!(one.vertices, vec![1, 2, 3, 6]);
+        assert_eq!(pairs(&one.edges), vec![(1, 2), (2, 3), (6, 2)]);
+        assert_eq!(one.edges[2].data, 62.0);
+
+        let two = two.unwrap();
+        assert_eq!(two.vertices, vec![0, 1, 2, 3, 4, 6]);
+        assert_eq!(pairs(&two.edges), vec![(0, 1), (1, 2), (2, 3), (3, 4), (6, 2)]);
+
+        let limited = limited.unwrap();
+        assert_eq!(limited.vertices, vec![1, 2, 3]);
+        assert_eq!(pairs(&limited.edges), vec![(1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn k_hop0() {
+        let communicatoner = com_for_test(34, 35, 0);
+        let edges = vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (6, 2)].into_iter().map(|(from, to)| {
+            Edge { from : from, to : to, data : (from * 10 + to) as f32 }
+        }).collect();
+        check(edges, vec![2], &communicatoner);
+    }
+
+    #[test]
+    fn k_hop1() {
+        let communicatoner = com_for_test(34, 35, 1);
+        check(vec![], vec![], &communicatoner);
+    }
+}
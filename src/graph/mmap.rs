@@ -0,0 +1,415 @@
+use bincode::{Encode, Decode};
+use memmap2::{Mmap, MmapMut};
+use rayon::iter::IntoParallelIterator;
+
+use crate::common::base_structure::{Vid, Edge};
+use crate::common::util::Serilazer;
+use crate::io::{FileRead, FromArrow, ReadOption};
+use crate::parallel::server::MyMpi;
+
+use super::{Graph, SeqPartition, NearGraph, GraphInfo, intersect};
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::marker::PhantomData;
+use std::fmt::Debug;
+
+/// 可以按定长字节存放在磁盘列里的边数据，统一使用小端序
+pub trait FixedWidth : Sized {
+    const WIDTH : usize;
+    fn write_to(&self, buf : &mut [u8]);
+    fn read_from(buf : &[u8]) -> Self;
+}
+
+macro_rules! impl_fixed_width {
+    ($($t:ty),*) => {
+        $(
+            impl FixedWidth for $t {
+                const WIDTH : usize = std::mem::size_of::<$t>();
+                fn write_to(&self, buf : &mut [u8]) {
+                    buf.copy_from_slice(&self.to_le_bytes());
+                }
+                fn read_from(buf : &[u8]) -> Self {
+                    <$t>::from_le_bytes(buf.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_width!(i32, u32, i64, u64, f32, f64);
+
+/// 元信息文件里保存的内容：(partitions, rank, graph_info, partition, sorted)
+type MetaData<PART> = (usize, usize, GraphInfo, PART, bool);
+
+fn file_name(path : &str, rank : usize, column : &str) -> String {
+    format!("{path}.{rank}.{column}")
+}
+
+fn write_meta<PART : Encode>(path : &str, meta : (usize, usize, &GraphInfo, &PART, bool)) {
+    let serilazer = Serilazer::new();
+    std::fs::write(file_name(path, meta.1, "meta"), serilazer.encode(meta)).expect("write meta failed!");
+}
+
+fn create_column(path : &str, rank : usize, column : &str, len : usize) -> MmapMut {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_name(path, rank, column))
+        .expect("create column failed!");
+    file.set_len(len as u64).unwrap();
+    unsafe { MmapMut::map_mut(&file).expect("mmap column failed!") }
+}
+
+fn open_column(path : &str, rank : usize, column : &str) -> Mmap {
+    let file = File::open(file_name(path, rank, column)).expect("open column failed!");
+    unsafe { Mmap::map(&file).expect("mmap column failed!") }
+}
+
+/// 把映射的字节看作 T 的数组。映射的起始地址按页对齐，要求本机为小端序
+fn as_slice<T>(map : &Mmap) -> &[T] {
+    unsafe { std::slice::from_raw_parts(map.as_ptr() as *const T, map.len() / std::mem::size_of::<T>()) }
+}
+
+/// 只读的磁盘图，邻接表以 CSR 的形式存放在内存映射的定长列里：
+/// `{path}.{rank}.offsets` 每个顶点邻接表的起始位置（u64，顶点数 + 1 个），
+/// `{path}.{rank}.targets` 邻居 id（u32），`{path}.{rank}.edata` 边数据（每条 EDATA::WIDTH 字节），
+/// `{path}.{rank}.out` 边的方向（u8，1 表示该顶点是边的起点，同 NearEdge::out），
+/// `{path}.{rank}.meta` 划分信息。顶点的范围和 NearGraph::g 一致。
+#[derive(Debug)]
+pub struct MmapGraph<EDATA, PART> {
+    pub graph_info : GraphInfo,
+    partition : PART,
+    sorted : bool,
+    offsets : Mmap,
+    targets : Mmap,
+    edata : Mmap,
+    out : Mmap,
+    _edata : PhantomData<EDATA>,
+}
+
+impl<EDATA, PART> MmapGraph<EDATA, PART>
+where
+    PART : SeqPartition + Sync + Encode + Decode + 'static,
+    EDATA : FixedWidth + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    /// 把 NearGraph 写成磁盘格式
+    pub fn write(graph : &NearGraph<EDATA, PART>, path : String, communication : &impl MyMpi) {
+        let cluster_info = communication.get_cluster_info();
+        let rank = cluster_info.rank;
+
+        let mut offsets = BufWriter::new(File::create(file_name(&path, rank, "offsets")).unwrap());
+        let mut targets = BufWriter::new(File::create(file_name(&path, rank, "targets")).unwrap());
+        let mut edata = BufWriter::new(File::create(file_name(&path, rank, "edata")).unwrap());
+        let mut out = BufWriter::new(File::create(file_name(&path, rank, "out")).unwrap());
+        let mut buf = vec![0u8; EDATA::WIDTH];
+
+        let mut offset = 0u64;
+        offsets.write_all(&offset.to_le_bytes()).unwrap();
+        for nbr in graph.g.iter() {
+            for edge in nbr.iter() {
+                targets.write_all(&edge.to.to_le_bytes()).unwrap();
+                edge.data.write_to(&mut buf);
+                edata.write_all(&buf).unwrap();
+                out.write_all(&[edge.out as u8]).unwrap();
+            }
+            offset += nbr.len() as u64;
+            offsets.write_all(&offset.to_le_bytes()).unwrap();
+        }
+        offsets.flush().unwrap();
+        targets.flush().unwrap();
+        edata.flush().unwrap();
+        out.flush().unwrap();
+
+        write_meta(&path, (cluster_info.partitions, rank, &graph.graph_info, &graph.partition, graph.sorted));
+    }
+
+    /// 直接从文件读边、划分后写成磁盘格式并打开，不在内存里构建邻接表。
+    /// 注意读边和 impl_partition 都以整个 Vec 为单位，本 rank 读到和分到的边仍会同时放在内存里，
+    /// 另外还需要一个 vertex_num + 1 长的 u64 数组；两遍写列（先计数再填充）直接写进映射的文件，
+    /// 省下的只是 NearGraph 中每条边两份 NearEdge 的邻接表
+    pub fn build(reader : &impl FileRead, file : String, option : ReadOption, path : String, communication : &impl MyMpi) -> Self
+    where
+        EDATA : FromArrow,
+    {
+        let edges = reader.read_edge::<EDATA>(file, option);
+        let graph_info = GraphInfo::from(&edges, communication);
+        let cluster_info = communication.get_cluster_info();
+        let partition = PART::new(vec![], &graph_info, cluster_info);
+        let edges = partition.impl_partition(edges, communication);
+        let rank = cluster_info.rank;
+        let vertex_num = graph_info.vertex_num as usize;
+
+        // 和 NearGraph 一样每条边在两个端点的邻接表里各出现一次，out 列记录方向
+        let mut cursor = vec![0u64; vertex_num + 1];
+        edges.iter().for_each(|edge| {
+            cursor[edge.from as usize + 1] += 1;
+            cursor[edge.to as usize + 1] += 1;
+        });
+        for i in 0..vertex_num {
+            cursor[i + 1] += cursor[i];
+        }
+        let entries = cursor[vertex_num] as usize;
+
+        let mut offsets = create_column(&path, rank, "offsets", (vertex_num + 1) * 8);
+        cursor.iter().enumerate().for_each(|(i, offset)| {
+            offsets[i * 8..(i + 1) * 8].copy_from_slice(&offset.to_le_bytes());
+        });
+        offsets.flush().unwrap();
+
+        let mut targets = create_column(&path, rank, "targets", entries * 4);
+        let mut edata = create_column(&path, rank, "edata", entries * EDATA::WIDTH);
+        let mut out = create_column(&path, rank, "out", entries);
+        let start = cursor.clone();
+        let mut put = |vid : Vid, to : Vid, data : &EDATA, is_out : bool| {
+            let pos = cursor[vid as usize] as usize;
+            cursor[vid as usize] += 1;
+            targets[pos * 4..(pos + 1) * 4].copy_from_slice(&to.to_le_bytes());
+            data.write_to(&mut edata[pos * EDATA::WIDTH..(pos + 1) * EDATA::WIDTH]);
+            out[pos] = is_out as u8;
+        };
+        edges.iter().for_each(|edge| {
+            put(edge.from, edge.to, &edge.data, true);
+            put(edge.to, edge.from, &edge.data, false);
+        });
+
+        // 和 NearGraph::sort_nbr 一样把每个邻接表按邻居 id 稳定排序，common_nbrs 等求交操作依赖这个顺序
+        let width = EDATA::WIDTH;
+        for v in 0..vertex_num {
+            let (begin, end) = (start[v] as usize, start[v + 1] as usize);
+            let target = |pos : usize| Vid::from_le_bytes(targets[pos * 4..(pos + 1) * 4].try_into().unwrap());
+            let mut order : Vec<usize> = (begin..end).collect();
+            order.sort_by_key(|&pos| target(pos));
+            let old_targets = targets[begin * 4..end * 4].to_vec();
+            let old_edata = edata[begin * width..end * width].to_vec();
+            let old_out = out[begin..end].to_vec();
+            order.into_iter().enumerate().for_each(|(i, pos)| {
+                let (dst, src) = (begin + i, pos - begin);
+                targets[dst * 4..(dst + 1) * 4].copy_from_slice(&old_targets[src * 4..(src + 1) * 4]);
+                edata[dst * width..(dst + 1) * width].copy_from_slice(&old_edata[src * width..(src + 1) * width]);
+                out[dst] = old_out[src];
+            });
+        }
+        targets.flush().unwrap();
+        edata.flush().unwrap();
+        out.flush().unwrap();
+
+        write_meta(&path, (cluster_info.partitions, rank, &graph_info, &partition, true));
+        MmapGraph::open(path, communication).unwrap()
+    }
+
+    /// 打开磁盘图。所有 rank 的文件都存在且集群规模一致时才返回 Some，否则所有 rank 都返回 None
+    pub fn open(path : String, communication : &impl MyMpi) -> Option<Self> {
+        let cluster_info = communication.get_cluster_info();
+        let rank = cluster_info.rank;
+
+        // 元信息文件不存在或无法解码都视为不可用
+        let meta = std::fs::read(file_name(&path, rank, "meta")).ok().and_then(|bytes| {
+            Serilazer::new().try_decode::<MetaData<PART>>(&bytes)
+        });
+        let matched = match &meta {
+            Some((partitions, meta_rank, ..)) => *partitions == cluster_info.partitions && *meta_rank == rank,
+            None => false,
+        };
+        if !communication.reduce(matched, |a, b| a && b) {
+            return None;
+        }
+
+        let (_, _, graph_info, partition, sorted) = meta.unwrap();
+        Some(MmapGraph {
+            graph_info : graph_info,
+            partition : partition,
+            sorted : sorted,
+            offsets : open_column(&path, rank, "offsets"),
+            targets : open_column(&path, rank, "targets"),
+            edata : open_column(&path, rank, "edata"),
+            out : open_column(&path, rank, "out"),
+            _edata : PhantomData,
+        })
+    }
+
+    fn range(&self, id : usize) -> (usize, usize) {
+        let offsets = as_slice::<u64>(&self.offsets);
+        (offsets[id] as usize, offsets[id + 1] as usize)
+    }
+
+    /// 顶点 id 的邻居，直接指向映射的内存
+    pub fn nbr(&self, id : usize) -> &[Vid] {
+        let (begin, end) = self.range(id);
+        &as_slice::<Vid>(&self.targets)[begin..end]
+    }
+
+    /// 顶点 id 的第 index 条边的数据
+    pub fn edge_data(&self, id : usize, index : usize) -> EDATA {
+        let (begin, _) = self.range(id);
+        let pos = begin + index;
+        EDATA::read_from(&self.edata[pos * EDATA::WIDTH..(pos + 1) * EDATA::WIDTH])
+    }
+
+    /// 顶点 id 的第 index 条边是否以 id 为起点
+    pub fn is_out(&self, id : usize, index : usize) -> bool {
+        let (begin, _) = self.range(id);
+        self.out[begin + index] != 0
+    }
+}
+
+impl<EDATA, PART> Graph for MmapGraph<EDATA, PART>
+where
+    PART : SeqPartition + Sync + Encode + Decode + 'static,
+    EDATA : FixedWidth + Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    type PART = PART;
+
+    fn local_vertexs(&self) -> Vid {
+        (as_slice::<u64>(&self.offsets).len() - 1) as Vid
+    }
+
+    fn get_array<T>(&self, init_data : T) -> Vec<T>
+    where
+        T : Clone
+    {
+        vec![init_data; (self.partition.end_id() - self.partition.start_id()) as usize]
+    }
+
+    fn degrees(&self) -> Vec<Vid> {
+        (self.partition.start_id()..self.partition.end_id()).map(|i| {
+            let (begin, end) = self.range(i as usize);
+            (end - begin) as Vid
+        }).collect()
+    }
+
+    fn partition(&self) -> &Self::PART {
+        &self.partition
+    }
+
+    fn for_each_nbr(&self, id : usize, mut f : impl FnMut(Vid)) {
+        self.nbr(id).iter().for_each(|&to| f(to));
+    }
+
+    fn for_each_nbr_range(&self, id : usize, lo : usize, hi : usize, mut f : impl FnMut(Vid)) {
+        self.nbr(id)[lo..hi].iter().for_each(|&to| f(to));
+    }
+
+    fn find_nbr(&self, id : usize, mut pred : impl FnMut(Vid) -> bool) -> Option<Vid> {
+        self.nbr(id).iter().cloned().find(|&to| pred(to))
+    }
+
+    fn has_edge(&self, u : Vid, v : Vid) -> bool {
+        let nbr = self.nbr(u as usize);
+        if self.sorted {
+            intersect::contains(nbr, v)
+        }else {
+            nbr.contains(&v)
+        }
+    }
+
+    fn common_nbrs(&self, u : Vid, v : Vid) -> Vec<Vid> {
+        assert!(self.sorted, "adjacency lists of the mmap graph are not sorted");
+        let mut res = vec![];
+        intersect::intersect(self.nbr(u as usize), self.nbr(v as usize), |x| res.push(x));
+        res
+    }
+
+    fn common_nbrs_count(&self, u : Vid, v : Vid) -> usize {
+        assert!(self.sorted, "adjacency lists of the mmap graph are not sorted");
+        let mut count = 0;
+        intersect::intersect(self.nbr(u as usize), self.nbr(v as usize), |_| count += 1);
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::{csv::CsvReader, example::*}, graph::SeqSPartition, algo::pagerank};
+
+    fn temp_file(name : &str) -> String {
+        std::env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    fn check(graph : NearGraph<MyEmpty, SeqSPartition>, communication : &impl MyMpi) {
+        let path = temp_file("first_rust_mmap_test");
+        MmapGraph::write(&graph, path.clone(), communication);
+        let mmap = MmapGraph::<MyEmpty, SeqSPartition>::open(path, communication).unwrap();
+
+        assert_eq!(mmap.local_vertexs(), graph.local_vertexs());
+        assert_eq!(mmap.degrees(), graph.degrees());
+        for id in 0..graph.g.len() {
+            assert_eq!(mmap.nbr(id).to_vec(), graph.nbr(id).iter().map(|x| x.to).collect::<Vec<_>>());
+            for (index, edge) in graph.nbr(id).iter().enumerate() {
+                assert_eq!(mmap.is_out(id, index), edge.is_out());
+            }
+        }
+
+        let corrupt = temp_file("first_rust_mmap_corrupt");
+        std::fs::write(file_name(&corrupt, communication.get_cluster_info().rank, "meta"), [0xffu8; 3]).unwrap();
+        assert!(MmapGraph::<MyEmpty, SeqSPartition>::open(corrupt, communication).is_none());
+
+        let pr_mmap = pagerank(mmap, communication);
+        let pr = pagerank(graph, communication);
+        assert_eq!(pr_mmap, pr);
+    }
+
+    #[test]
+    fn mmap0() {
+        let communicatoner = com_for_test(24, 25, 0);
+        let edges = sample_edges();
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, &communicatoner);
+        check(graph, &communicatoner);
+    }
+
+    #[test]
+    fn mmap1() {
+        let communicatoner = com_for_test(24, 25, 1);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(vec![], &communicatoner);
+        check(graph, &communicatoner);
+    }
+
+    #[test]
+    fn fixed_width() {
+        let mut buf = vec![0u8; f64::WIDTH];
+        2.5f64.write_to(&mut buf);
+        assert_eq!(f64::read_from(&buf), 2.5);
+        assert_eq!(MyEmpty::WIDTH, 0);
+    }
+
+    fn build_check(communication : &impl MyMpi) {
+        let csv = temp_file(&format!("first_rust_mmap_build_{}.csv", communication.get_cluster_info().rank));
+        let content = if communication.get_cluster_info().rank == 0 { "0,1\n1,2\n2,0\n" } else { "3,1\n" };
+        std::fs::write(&csv, content).unwrap();
+
+        let reader = CsvReader::new();
+        let mut read = ReadOption::default();
+        read.header = "from:uint,to:uint".into();
+        read.has_header = false;
+        let graph = MmapGraph::<MyEmpty, SeqSPartition>::build(&reader, csv, read, temp_file("first_rust_mmap_build"), communication);
+
+        assert_eq!(graph.graph_info.vertex_num, 4);
+        assert_eq!(graph.graph_info.edge_num, 4);
+        if communication.get_cluster_info().rank == 0 {
+            assert_eq!(graph.degrees(), vec![2, 3, 2]);
+            assert!(graph.has_edge(1, 3));
+            // 邻接表按邻居排序，方向保持 0 -> 1、1 -> 2、3 -> 1
+            assert_eq!(graph.nbr(1).to_vec(), vec![0, 2, 3]);
+            assert_eq!((0..3).map(|i| graph.is_out(1, i)).collect::<Vec<_>>(), vec![false, true, false]);
+            assert_eq!(graph.common_nbrs(0, 1), vec![2]);
+        }else {
+            assert_eq!(graph.degrees(), vec![1]);
+            assert_eq!(graph.nbr(3).to_vec(), vec![1]);
+        }
+    }
+
+    #[test]
+    fn mmap_build0() {
+        build_check(&com_for_test(26, 27, 0));
+    }
+
+    #[test]
+    fn mmap_build1() {
+        build_check(&com_for_test(26, 27, 1));
+    }
+}
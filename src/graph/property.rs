@@ -0,0 +1,237 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BooleanArray, StringArray, UInt32Array, Int32Array, Int64Array, UInt64Array, Float32Array, Float64Array};
+use arrow::record_batch::RecordBatch;
+use bincode::{Encode, Decode};
+
+use crate::common::base_structure::Vid;
+use crate::parallel::server::MyMpi;
+
+use super::vertex_cut::MirrorInfo;
+
+/// 可以作为顶点属性的类型，需要能导出为 Arrow 数组
+pub trait Property : Clone + Send + Sync + Encode + Decode + 'static {
+    fn to_arrow(values : &[Self]) -> ArrayRef;
+}
+
+macro_rules! impl_property {
+    ($($t:ty => $array:ty),*) => {
+        $(
+            impl Property for $t {
+                fn to_arrow(values : &[Self]) -> ArrayRef {
+                    Arc::new(<$array>::from(values.to_vec()))
+                }
+            }
+        )*
+    };
+}
+
+impl_property!(
+    i32 => Int32Array,
+    u32 => UInt32Array,
+    i64 => Int64Array,
+    u64 => UInt64Array,
+    f32 => Float32Array,
+    f64 => Float64Array,
+    bool => BooleanArray,
+    String => StringArray
+);
+
+/// 一列属性，init 用于图增长时填充新顶点
+struct Column<T> {
+    init : T,
+    values : Vec<T>,
+}
+
+/// 擦除了元素类型的属性列
+trait AnyColumn : Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn resize(&mut self, len : usize);
+    fn to_arrow(&self) -> ArrayRef;
+}
+
+impl<T : Property> AnyColumn for Column<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn resize(&mut self, len : usize) {
+        self.values.resize(len, self.init.clone());
+    }
+
+    fn to_arrow(&self) -> ArrayRef {
+        T::to_arrow(&self.values)
+    }
+}
+
+/// 挂在图上的顶点属性，按名字存放若干列，每列的长度等于本 rank 拥有的顶点数，
+/// 第 i 个元素对应顶点 start_id + i
+pub struct PropertyStore {
+    start_id : Vid,
+    len : usize,
+    columns : HashMap<String, Box<dyn AnyColumn>>,
+}
+
+impl Debug for PropertyStore {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PropertyStore")
+            .field("start_id", &self.start_id)
+            .field("len", &self.len)
+            .field("columns", &self.names())
+            .finish()
+    }
+}
+
+impl PropertyStore {
+    pub fn new(start_id : Vid, end_id : Vid) -> Self {
+        PropertyStore {
+            start_id : start_id,
+            len : (end_id - start_id) as usize,
+            columns : HashMap::new(),
+        }
+    }
+
+    /// 拥有的顶点范围变化后调整每一列的长度，新顶点填充为列的初始值
+    pub(crate) fn resize(&mut self, start_id : Vid, end_id : Vid) {
+        assert_eq!(start_id, self.start_id, "owned range can only grow at the end");
+        self.len = (end_id - start_id) as usize;
+        let len = self.len;
+        self.columns.values_mut().for_each(|column| column.resize(len));
+    }
+
+    /// 顶点 vid 在列中的下标
+    pub fn index(&self, vid : Vid) -> usize {
+        (vid - self.start_id) as usize
+    }
+
+    /// 新建一列，所有顶点初始化为 init。同名的列已存在时 panic
+    pub fn add<T : Property>(&mut self, name : &str, init : T) {
+        assert!(!self.columns.contains_key(name), "property {name} already exists");
+        let column = Column { values : vec![init.clone(); self.len], init : init };
+        self.columns.insert(name.to_string(), Box::new(column));
+    }
+
+    /// 删除一列，返回这一列是否存在
+    pub fn remove(&mut self, name : &str) -> bool {
+        self.columns.remove(name).is_some()
+    }
+
+    pub fn contains(&self, name : &str) -> bool {
+        self.columns.contains_key(name)
+    }
+
+    /// 所有列名，升序
+    pub fn names(&self) -> Vec<String> {
+        let mut names : Vec<String> = self.columns.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 取出一列，列不存在或者类型不是 T 时返回 None
+    pub fn get<T : Property>(&self, name : &str) -> Option<&[T]> {
+        self.columns.get(name)
+            .and_then(|column| column.as_any().downcast_ref::<Column<T>>())
+            .map(|column| column.values.as_slice())
+    }
+
+    pub fn get_mut<T : Property>(&mut self, name : &str) -> Option<&mut [T]> {
+        self.columns.get_mut(name)
+            .and_then(|column| column.as_any_mut().downcast_mut::<Column<T>>())
+            .map(|column| column.values.as_mut_slice())
+    }
+
+    /// 把一列导出为 Arrow 数组
+    pub fn to_arrow(&self, name : &str) -> Option<ArrayRef> {
+        self.columns.get(name).map(|column| column.to_arrow())
+    }
+
+    /// 把若干列连同顶点 id 导出为 RecordBatch，第一列为 "id"。names 里有不存在的列时返回 None
+    pub fn to_record_batch(&self, names : &[&str]) -> Option<RecordBatch> {
+        let ids : Vec<Vid> = (self.start_id..self.start_id + self.len as Vid).collect();
+        let mut columns : Vec<(String, ArrayRef)> = vec![("id".to_string(), Arc::new(UInt32Array::from(ids)))];
+        for &name in names {
+            columns.push((name.to_string(), self.to_arrow(name)?));
+        }
+        Some(RecordBatch::try_from_iter(columns).expect("build record batch failed!"))
+    }
+
+    /// 把一列的值同步到其他 rank 上的 mirror，返回本 rank 上每个 mirror 收到的 (vid, value)。
+    /// 所有 rank 需要同时调用
+    pub fn sync_to_mirrors<T : Property>(&self, name : &str, mirror : &MirrorInfo, communication : &impl MyMpi) -> Vec<(Vid, T)> {
+        let values = self.get::<T>(name).expect("property not found");
+        mirror.sync_to_mirrors(|vid| values[self.index(vid)].clone(), communication)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Array;
+    use crate::{parallel::server::*, io::example::*, graph::{Graph, NearGraph, SeqPartition, vertex_cut::GridPartition}, common::base_structure::Edge};
+
+    #[test]
+    fn columns() {
+        let mut store = PropertyStore::new(4, 7);
+        store.add::<f32>("rank", 1.0);
+        store.add::<String>("name", String::new());
+        assert!(store.get::<u32>("rank").is_none());
+        assert!(store.get::<f32>("missing").is_none());
+
+        let index = store.index(5);
+        store.get_mut::<f32>("rank").unwrap()[index] = 2.5;
+        store.get_mut::<String>("name").unwrap()[0] = "a".to_string();
+        assert_eq!(store.get::<f32>("rank").unwrap(), &[1.0, 2.5, 1.0]);
+        assert_eq!(store.names(), vec!["name".to_string(), "rank".to_string()]);
+
+        store.resize(4, 9);
+        assert_eq!(store.get::<f32>("rank").unwrap(), &[1.0, 2.5, 1.0, 1.0, 1.0]);
+
+        let batch = store.to_record_batch(&["rank", "name"]).unwrap();
+        assert_eq!(batch.num_rows(), 5);
+        assert_eq!(batch.num_columns(), 3);
+        let ids = batch.column(0).as_any().downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!(ids.value(0), 4);
+        let names = batch.column(2).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(names.value(0), "a");
+        assert!(store.to_record_batch(&["missing"]).is_none());
+
+        assert!(store.remove("rank"));
+        assert!(!store.remove("rank"));
+        assert!(!store.contains("rank"));
+    }
+
+    fn check_sync(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi) {
+        let mut graph = NearGraph::<MyEmpty, GridPartition>::new(edges, communication);
+        let mirror = MirrorInfo::new(&graph, communication);
+        let start_id = graph.partition().start_id();
+
+        let properties = graph.properties_mut();
+        properties.add::<u32>("label", 0);
+        properties.get_mut::<u32>("label").unwrap().iter_mut().enumerate().for_each(|(i, label)| *label = (start_id + i as Vid) * 10);
+
+        let synced = graph.properties().sync_to_mirrors::<u32>("label", &mirror, communication);
+        let expected : Vec<(Vid, u32)> = mirror.mirrors.iter().flatten().map(|&vid| (vid, vid * 10)).collect();
+        assert_eq!(synced, expected);
+    }
+
+    #[test]
+    fn property_sync0() {
+        let communicatoner = com_for_test(38, 39, 0);
+        let edges = sample_edges();
+        check_sync(edges, &communicatoner);
+    }
+
+    #[test]
+    fn property_sync1() {
+        let communicatoner = com_for_test(38, 39, 1);
+        check_sync(vec![], &communicatoner);
+    }
+}
@@ -0,0 +1,163 @@
+use bincode::{Encode, Decode};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, IndexedParallelIterator, ParallelIterator};
+
+use crate::common::base_structure::{Vid, Eid, Edge};
+use crate::parallel::server::MyMpi;
+
+use super::{SeqPartition, NearGraph, Graph};
+
+use std::fmt::{self, Display};
+
+/// 单个 rank 上的划分情况
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct RankReport {
+    pub rank : usize,
+
+    /// 本 rank 拥有（master）的顶点数
+    pub owned_vertices : Vid,
+
+    /// 本地出现但不属于本 rank 的顶点数（副本）
+    pub replicated_vertices : Vid,
+
+    /// 本地存储的边数
+    pub edges : Eid,
+
+    /// 本地存储、同时也存在其他 rank 上的边数
+    pub replicated_edges : Eid,
+
+    /// 本地存储、两个端点属于不同 rank 的边数
+    pub cut_edges : Eid,
+}
+
+/// 汇总到 rank 0 的划分质量报告
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct PartitionReport {
+    pub ranks : Vec<RankReport>,
+
+    pub vertex_num : Vid,
+
+    pub edge_num : Eid,
+
+    /// 被切断的边占全部边的比例
+    pub cut_edge_ratio : f64,
+
+    /// 平均每个顶点的副本数（含 master）
+    pub replication_factor : f64,
+
+    /// 最大本地边数 / 平均本地边数
+    pub load_imbalance : f64,
+}
+
+impl RankReport {
+    fn from<EDATA, PART>(graph : &NearGraph<EDATA, PART>, rank : usize) -> Self
+    where
+        PART : SeqPartition + Sync,
+        EDATA : Clone + Send + Sync,
+        Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+    {
+        let partition = graph.partition();
+        let (entries, cut_entries, replicated_vertices) = graph.g
+            .par_iter()
+            .enumerate()
+            .map(|(id, nbr)| {
+                let owner = partition.vertex_partition(&(id as Vid));
+                let cut = nbr.iter().filter(|edge| partition.vertex_partition(&edge.to) != owner).count();
+                let replicated = (owner != rank && !nbr.is_empty()) as usize;
+                (nbr.len() as Eid, cut as Eid, replicated as Vid)
+            })
+            .reduce(|| (0, 0, 0), |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2));
+
+        // 每条本地边在邻接表里出现两次
+        let edges = entries / 2;
+        let cut_edges = cut_entries / 2;
+        // edge-cut 下被切断的边在两个端点的 rank 上各存一份
+        let replicated_edges = if partition.vertex_cut() { 0 } else { cut_edges };
+
+        RankReport {
+            rank : rank,
+            owned_vertices : partition.end_id() - partition.start_id(),
+            replicated_vertices : replicated_vertices,
+            edges : edges,
+            replicated_edges : replicated_edges,
+            cut_edges : cut_edges,
+        }
+    }
+}
+
+/// 统计每个 rank 的划分情况并汇总到 rank 0，rank 0 返回 Some，其余 rank 返回 None
+pub fn partition_report<EDATA, PART>(graph : &NearGraph<EDATA, PART>, communication : &impl MyMpi) -> Option<PartitionReport>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    let rank = communication.get_cluster_info().rank;
+    let ranks = communication.gather(RankReport::from(graph, rank), 0);
+    if rank != 0 {
+        return None;
+    }
+
+    let vertex_num = graph.graph_info.vertex_num;
+    let edge_num = graph.graph_info.edge_num;
+    let stored : Eid = ranks.iter().map(|x| x.edges).sum();
+    let cut : Eid = ranks.iter().map(|x| x.cut_edges).sum();
+    let unique_cut = if graph.partition().vertex_cut() { cut } else { cut / 2 };
+    let replicas : Vid = ranks.iter().map(|x| x.replicated_vertices).sum();
+    let max_edges = ranks.iter().map(|x| x.edges).max().unwrap_or_default();
+    let avg_edges = stored as f64 / ranks.len() as f64;
+
+    Some(PartitionReport {
+        vertex_num : vertex_num,
+        edge_num : edge_num,
+        cut_edge_ratio : if edge_num == 0 { 0.0 } else { unique_cut as f64 / edge_num as f64 },
+        replication_factor : if vertex_num == 0 { 0.0 } else { (vertex_num + replicas) as f64 / vertex_num as f64 },
+        load_imbalance : if stored == 0 { 1.0 } else { max_edges as f64 / avg_edges },
+        ranks : ranks,
+    })
+}
+
+impl Display for PartitionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "vertexs: {}, edges: {}", self.vertex_num, self.edge_num)?;
+        writeln!(f, "{:>6} {:>12} {:>12} {:>12} {:>12} {:>12}", "rank", "owned", "replicas", "edges", "replicated", "cut")?;
+        for r in self.ranks.iter() {
+            writeln!(f, "{:>6} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                r.rank, r.owned_vertices, r.replicated_vertices, r.edges, r.replicated_edges, r.cut_edges)?;
+        }
+        writeln!(f, "cut-edge ratio: {:.4}", self.cut_edge_ratio)?;
+        writeln!(f, "replication factor: {:.4}", self.replication_factor)?;
+        write!(f, "load imbalance: {:.4}", self.load_imbalance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::SeqSPartition};
+
+    #[test]
+    fn report0() {
+        let communicatoner = com_for_test(14, 15, 0);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(sample_edges(), &communicatoner);
+
+        let report = partition_report(&graph, &communicatoner).unwrap();
+        println!("{report}");
+        assert_eq!(report.ranks[0], RankReport {
+            rank : 0, owned_vertices : 4, replicated_vertices : 1, edges : 6, replicated_edges : 1, cut_edges : 1
+        });
+        assert_eq!(report.ranks[1], RankReport {
+            rank : 1, owned_vertices : 2, replicated_vertices : 1, edges : 2, replicated_edges : 1, cut_edges : 1
+        });
+        assert_eq!(report.cut_edge_ratio, 1.0 / 7.0);
+        assert_eq!(report.replication_factor, 8.0 / 6.0);
+        assert_eq!(report.load_imbalance, 1.5);
+    }
+
+    #[test]
+    fn report1() {
+        let communicatoner = com_for_test(14, 15, 1);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(vec![], &communicatoner);
+
+        assert!(partition_report(&graph, &communicatoner).is_none());
+    }
+}
@@ -0,0 +1,121 @@
+use bincode::{Encode, Decode};
+use rayon::iter::IntoParallelIterator;
+
+use crate::common::base_structure::Edge;
+use crate::common::util::Serilazer;
+use crate::parallel::server::MyMpi;
+
+use super::{SeqPartition, NearGraph, NearEdge, GraphInfo};
+use super::property::PropertyStore;
+
+use std::fmt::Debug;
+
+/// 快照文件里保存的内容：(partitions, rank, graph_info, partition, sorted, g)
+type SnapshotData<EDATA, PART> = (usize, usize, GraphInfo, PART, bool, Vec<Vec<NearEdge<EDATA>>>);
+
+fn snapshot_path(path : &str, rank : usize) -> String {
+    format!("{path}.{rank}")
+}
+
+impl<EDATA, PART> NearGraph<EDATA, PART>
+where
+    PART : SeqPartition + Sync + Encode + Decode + 'static,
+    EDATA : Clone + Send + Sync + Debug + Encode + Decode + 'static,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    /// 每个 rank 把建好的图写到 `{path}.{rank}`，属性列不会保存
+    pub fn save_snapshot(&self, path : String, communication : &impl MyMpi) {
+        let cluster_info = communication.get_cluster_info();
+        let serilazer = Serilazer::new();
+        let bytes = serilazer.encode((
+            cluster_info.partitions,
+            cluster_info.rank,
+            &self.graph_info,
+            &self.partition,
+            self.sorted,
+            &self.g,
+        ));
+        let file = snapshot_path(&path, cluster_info.rank);
+        std::fs::write(&file, bytes).expect("write snapshot failed!");
+        println!("save snapshot: {file}");
+    }
+
+    /// 从 `{path}.{rank}` 恢复图。所有 rank 的快照都存在且集群规模一致时才返回 Some，
+    /// 否则所有 rank 都返回 None
+    pub fn load_snapshot(path : String, communication : &impl MyMpi) -> Option<Self> {
+        let cluster_info = communication.get_cluster_info();
+        let file = snapshot_path(&path, cluster_info.rank);
+
+        // 文件不存在或无法解码都视为不可用
+        let data = std::fs::read(&file).ok().and_then(|bytes| {
+            Serilazer::new().try_decode::<SnapshotData<EDATA, PART>>(&bytes)
+        });
+        let matched = match &data {
+            Some((partitions, rank, ..)) => *partitions == cluster_info.partitions && *rank == cluster_info.rank,
+            None => false,
+        };
+
+        // 只要有一个 rank 无法恢复，就全部重新建图
+        if !communication.reduce(matched, |a, b| a && b) {
+            println!("snapshot {file} not usable");
+            return None;
+        }
+
+        let (_, _, graph_info, partition, sorted, g) = data.unwrap();
+        println!("load snapshot: {file}");
+        let properties = PropertyStore::new(partition.start_id(), partition.end_id());
+        Some(NearGraph {
+            graph_info : graph_info,
+            g : g,
+            partition : partition,
+            sorted : sorted,
+            properties : properties,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::{Graph, SeqSPartition}};
+
+    fn snapshot_file(name : &str) -> String {
+        std::env::temp_dir().join(name).to_str().unwrap().to_string()
+    }
+
+    fn check(graph : &NearGraph<MyEDATA, SeqSPartition>, communication : &impl MyMpi) {
+        let path = snapshot_file("first_rust_snapshot_test");
+        graph.save_snapshot(path.clone(), communication);
+
+        let loaded = NearGraph::<MyEDATA, SeqSPartition>::load_snapshot(path, communication).unwrap();
+        assert_eq!(loaded.graph_info.vertex_num, graph.graph_info.vertex_num);
+        assert_eq!(loaded.graph_info.edge_num, graph.graph_info.edge_num);
+        assert_eq!(loaded.partition().start_id(), graph.partition().start_id());
+        assert_eq!(loaded.partition().end_id(), graph.partition().end_id());
+        assert_eq!(format!("{:?}", loaded.g), format!("{:?}", graph.g));
+
+        let missing = NearGraph::<MyEDATA, SeqSPartition>::load_snapshot(snapshot_file("first_rust_snapshot_missing"), communication);
+        assert!(missing.is_none());
+
+        let corrupt = snapshot_file("first_rust_snapshot_corrupt");
+        std::fs::write(snapshot_path(&corrupt, communication.get_cluster_info().rank), [0xffu8; 3]).unwrap();
+        assert!(NearGraph::<MyEDATA, SeqSPartition>::load_snapshot(corrupt, communication).is_none());
+    }
+
+    #[test]
+    fn snapshot0() {
+        let communicatoner = com_for_test(22, 23, 0);
+        let edges = vec![(0, 1), (1, 2), (2, 3), (3, 0)].into_iter().map(|(from, to)| {
+            Edge { from : from, to : to, data : MyEDATA { i32_data : from as i32, f32_data : to as f32, str_data : format!("{from}-{to}") } }
+        }).collect();
+        let graph = NearGraph::<MyEDATA, SeqSPartition>::new(edges, &communicatoner);
+        check(&graph, &communicatoner);
+    }
+
+    #[test]
+    fn snapshot1() {
+        let communicatoner = com_for_test(22, 23, 1);
+        let graph = NearGraph::<MyEDATA, SeqSPartition>::new(vec![], &communicatoner);
+        check(&graph, &communicatoner);
+    }
+}
@@ -0,0 +1,200 @@
+use bincode::{Encode, Decode};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::common::base_structure::{Vid, Eid, Edge};
+use crate::parallel::server::MyMpi;
+use crate::algo::wcc::wcc;
+
+use super::{SeqPartition, NearGraph, Graph};
+
+use std::fmt::Debug;
+
+/// 全局的图统计信息。按无向图统计，(u, v) 与 (v, u) 视为同一条边。
+/// 假设 owned 顶点的邻接表是完整的（edge-cut 划分，如 SeqSPartition）。
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct GraphStats {
+    /// 最大顶点 id + 1
+    pub vertex_num : Vid,
+
+    pub edge_num : Eid,
+
+    /// 度数不为 0 的顶点数
+    pub non_isolated_vertices : Vid,
+
+    pub self_loops : Eid,
+
+    /// 重复出现的边数，每组平行边中除第一条以外都计入
+    pub duplicate_edges : Eid,
+
+    pub max_degree : Vid,
+
+    /// 非孤立顶点的平均度数
+    pub avg_degree : f64,
+
+    /// degree_histogram[0] 为度数为 0 的顶点数，degree_histogram[i] 为度数在 [2^(i-1), 2^i) 的顶点数
+    pub degree_histogram : Vec<Vid>,
+
+    /// 非孤立顶点构成的连通分量个数
+    pub components : Vid,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+struct LocalStats {
+    non_isolated_vertices : Vid,
+    self_loops : Eid,
+    duplicate_edges : Eid,
+    max_degree : Vid,
+    degree_sum : Eid,
+    degree_histogram : Vec<Vid>,
+}
+
+impl LocalStats {
+    fn empty() -> Self {
+        LocalStats {
+            non_isolated_vertices : 0,
+            self_loops : 0,
+            duplicate_edges : 0,
+            max_degree : 0,
+            degree_sum : 0,
+            degree_histogram : vec![0; Vid::BITS as usize + 1],
+        }
+    }
+
+    fn merge(mut self, other : Self) -> Self {
+        self.non_isolated_vertices += other.non_isolated_vertices;
+        self.self_loops += other.self_loops;
+        self.duplicate_edges += other.duplicate_edges;
+        self.max_degree = self.max_degree.max(other.max_degree);
+        self.degree_sum += other.degree_sum;
+        self.degree_histogram.iter_mut().zip(other.degree_histogram.iter()).for_each(|(a, b)| *a += b);
+        self
+    }
+
+    fn vertex(id : Vid, tos : &mut Vec<Vid>) -> Self {
+        let mut stats = LocalStats::empty();
+        let degree = tos.len() as Vid;
+        stats.degree_histogram[(Vid::BITS - degree.leading_zeros()) as usize] += 1;
+        if degree == 0 {
+            return stats;
+        }
+        stats.non_isolated_vertices = 1;
+        stats.max_degree = degree;
+        stats.degree_sum = degree as Eid;
+
+        tos.sort_unstable();
+        let mut i = 0;
+        while i < tos.len() {
+            let mut j = i;
+            while j < tos.len() && tos[j] == tos[i] {
+                j += 1;
+            }
+            let count = (j - i) as Eid;
+            if tos[i] == id {
+                // 自环在自己的邻接表里出现两次
+                stats.self_loops += count / 2;
+                stats.duplicate_edges += count / 2 - 1;
+            }else if tos[i] > id {
+                // 每条边只在较小的端点上统计重复
+                stats.duplicate_edges += count - 1;
+            }
+            i = j;
+        }
+        stats
+    }
+}
+
+/// 计算全局统计信息，每个 rank 都返回相同的结果
+pub fn graph_stats<EDATA, PART>(graph : &NearGraph<EDATA, PART>, communication : &impl MyMpi) -> GraphStats
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    let start_id = graph.partition().start_id();
+    let end_id = graph.partition().end_id();
+
+    let local = (start_id..end_id)
+        .into_par_iter()
+        .map(|id| {
+            let mut tos : Vec<Vid> = graph.nbr(id as usize).iter().map(|edge| edge.to).collect();
+            LocalStats::vertex(id, &mut tos)
+        })
+        .reduce(LocalStats::empty, LocalStats::merge);
+
+    let global = communication.reduce(local, LocalStats::merge);
+    let components = count_components(graph, communication);
+
+    let mut degree_histogram = global.degree_histogram;
+    while degree_histogram.len() > 1 && *degree_histogram.last().unwrap() == 0 {
+        degree_histogram.pop();
+    }
+
+    GraphStats {
+        vertex_num : graph.graph_info.vertex_num,
+        edge_num : graph.graph_info.edge_num,
+        non_isolated_vertices : global.non_isolated_vertices,
+        self_loops : global.self_loops,
+        duplicate_edges : global.duplicate_edges,
+        max_degree : global.max_degree,
+        avg_degree : if global.non_isolated_vertices == 0 {
+            0.0
+        }else {
+            global.degree_sum as f64 / global.non_isolated_vertices as f64
+        },
+        degree_histogram : degree_histogram,
+        components : components,
+    }
+}
+
+/// 用 wcc 的标签统计非孤立顶点的连通分量个数：标签等于自身且度数不为 0 的顶点各代表一个分量
+fn count_components<EDATA, PART>(graph : &NearGraph<EDATA, PART>, communication : &impl MyMpi) -> Vid
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    let start_id = graph.partition().start_id();
+    let label = wcc(graph, communication).label;
+    let roots = label.iter().enumerate()
+        .filter(|&(i, &l)| l == start_id + i as Vid && !graph.nbr(l as usize).is_empty())
+        .count() as Vid;
+    communication.reduce(roots, |a, b| a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::SeqSPartition};
+
+    fn test_edges() -> Vec<Edge<MyEmpty>> {
+        // 0-1-2 成环，1-2 重复一次（反向），3 自环两次，4-5-7 成链，6 孤立
+        edges(&[(0, 1), (1, 2), (2, 0), (2, 1), (3, 3), (3, 3), (4, 5), (7, 5)])
+    }
+
+    fn check(stats : GraphStats) {
+        assert_eq!(stats.vertex_num, 8);
+        assert_eq!(stats.edge_num, 8);
+        assert_eq!(stats.non_isolated_vertices, 7);
+        assert_eq!(stats.self_loops, 2);
+        assert_eq!(stats.duplicate_edges, 2);
+        assert_eq!(stats.max_degree, 4);
+        assert_eq!(stats.avg_degree, 16.0 / 7.0);
+        // 度数: 0:2 1:3 2:3 3:4 4:1 5:2 6:0 7:1
+        assert_eq!(stats.degree_histogram, vec![1, 2, 4, 1]);
+        assert_eq!(stats.components, 3);
+    }
+
+    #[test]
+    fn stats0() {
+        let communicatoner = com_for_test(16, 17, 0);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(test_edges(), &communicatoner);
+        check(graph_stats(&graph, &communicatoner));
+    }
+
+    #[test]
+    fn stats1() {
+        let communicatoner = com_for_test(16, 17, 1);
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(vec![], &communicatoner);
+        check(graph_stats(&graph, &communicatoner));
+    }
+}
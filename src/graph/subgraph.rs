@@ -0,0 +1,125 @@
+use bincode::{Encode, Decode};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::common::base_structure::{Vid, Eid, Edge};
+use crate::parallel::server::MyMpi;
+
+use super::{SeqPartition, NearGraph, GraphInfo};
+use super::clean::CleanOption;
+
+use std::fmt::Debug;
+
+/// subgraph 的结果
+pub struct Subgraph<EDATA, PART>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    pub graph : NearGraph<EDATA, PART>,
+
+    /// origin[i] 是新图中本 rank 拥有的顶点 start_id + i 在原图中的 id
+    pub origin : Vec<Vid>,
+}
+
+impl<EDATA, PART> NearGraph<EDATA, PART>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    /// 抽取子图，所有 rank 需要同时调用。
+    /// vertex_pred 只对本 rank 拥有的顶点调用；保留两个端点都被保留、且满足 edge_pred 的边。
+    /// compact 为 true 时把保留的顶点按原 id 的顺序重新编号为 0..n，否则沿用原 id，vertex_num 不变
+    pub fn subgraph(
+        &self,
+        vertex_pred : impl Fn(Vid) -> bool + Sync,
+        edge_pred : impl Fn(Vid, Vid, &EDATA) -> bool + Sync,
+        compact : bool,
+        communication : &impl MyMpi,
+    ) -> Subgraph<EDATA, PART> {
+        let partitions = communication.partitions();
+
+        let keep : Vec<bool> = {
+            let local : Vec<bool> = (self.partition.start_id()..self.partition.end_id()).into_par_iter().map(|v| vertex_pred(v)).collect();
+            let msgs = vec![local; partitions];
+            let recv = communication.send_recv::<Vec<bool>>(msgs);
+            recv.into_iter().flatten().collect()
+        };
+
+        // 压缩后的新 id，被删掉的顶点为 Vid::MAX
+        let (vertex_num, new_id) = if compact {
+            let mut next : Vid = 0;
+            let new_id : Vec<Vid> = keep.iter().map(|&k| {
+                if k {
+                    next += 1;
+                    next - 1
+                }else {
+                    Vid::MAX
+                }
+            }).collect();
+            (next, new_id)
+        }else {
+            (self.graph_info.vertex_num, vec![])
+        };
+        let map = |v : Vid| if compact { new_id[v as usize] } else { v };
+
+        let edges : Vec<Edge<EDATA>> = (0..self.g.len()).into_par_iter().filter(|&u| keep[u]).flat_map_iter(|u| {
+            let mut res = vec![];
+            self.for_each_unique_edge(u as Vid, |from, to, data| {
+                if keep[from as usize] && keep[to as usize] && edge_pred(from, to, data) {
+                    res.push(Edge { from : map(from), to : map(to), data : data.clone() });
+                }
+            });
+            res
+        }).collect();
+
+        let edge_num = communication.reduce(edges.len() as Eid, |a, b| a + b);
+        let graph_info = GraphInfo { vertex_num : vertex_num, edge_num : edge_num };
+        let mut option = CleanOption::default();
+        option.sort_nbr = self.sorted;
+        let graph = NearGraph::<EDATA, PART>::build(graph_info, edges, communication, &option);
+
+        let start_id = graph.partition.start_id();
+        let end_id = graph.partition.end_id();
+        let origin = if compact {
+            let kept : Vec<Vid> = (0..keep.len() as Vid).filter(|&v| keep[v as usize]).collect();
+            kept[start_id as usize..end_id as usize].to_vec()
+        }else {
+            (start_id..end_id).collect()
+        };
+
+        Subgraph { graph : graph, origin : origin }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::weighted_edges, graph::{Graph, SeqSPartition}};
+
+    fn check(edges : Vec<Edge<f32>>, communication : &impl MyMpi, expected : [(Vec<Vid>, Vec<Vid>); 2]) {
+        let graph = NearGraph::<f32, SeqSPartition>::new(edges, communication);
+
+        for (compact, (origin, degrees)) in [false, true].into_iter().zip(expected) {
+            let sub = graph.subgraph(|v| v != 2, |_, _, &w| w < 5.0, compact, communication);
+            assert_eq!(sub.graph.graph_info.vertex_num, if compact { 5 } else { 6 });
+            assert_eq!(sub.graph.graph_info.edge_num, 5);
+            assert_eq!(sub.origin, origin);
+            assert_eq!(sub.graph.degrees(), degrees);
+        }
+    }
+
+    #[test]
+    fn subgraph0() {
+        let communicatoner = com_for_test(32, 33, 0);
+        let edges = weighted_edges(&[(0, 1, 1.0), (1, 2, 1.0), (2, 3, 1.0), (3, 4, 1.0), (4, 5, 9.0), (5, 0, 1.0), (1, 4, 1.0), (3, 3, 1.0)]);
+        check(edges, &communicatoner, [(vec![0, 1, 2, 3], vec![2, 2, 0, 3]), (vec![0, 1, 3], vec![2, 2, 3])]);
+    }
+
+    #[test]
+    fn subgraph1() {
+        let communicatoner = com_for_test(32, 33, 1);
+        check(vec![], &communicatoner, [(vec![4, 5], vec![2, 1]), (vec![4, 5], vec![2, 1])]);
+    }
+}
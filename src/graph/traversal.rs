@@ -0,0 +1,199 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+use crate::common::base_structure::Vid;
+use crate::traits::Traversal;
+
+use super::{Graph, SeqPartition};
+
+/// 默认每次取出的块大小
+const DEFAULT_CHUNK_SIZE : u32 = 1024;
+
+/// 多个线程共享的遍历位置，每个线程用 fetch_add 取走下一个块
+struct Cursor {
+    next : AtomicUsize,
+    chunk_size : AtomicU32,
+    stop : AtomicBool,
+}
+
+impl Cursor {
+    fn new() -> Self {
+        Cursor {
+            next : AtomicUsize::new(0),
+            chunk_size : AtomicU32::new(DEFAULT_CHUNK_SIZE),
+            stop : AtomicBool::new(false),
+        }
+    }
+
+    fn reset(&self, chunk_size : u32) {
+        assert!(chunk_size > 0, "chunk_size must be positive");
+        self.chunk_size.store(chunk_size, Ordering::Relaxed);
+        self.stop.store(false, Ordering::Relaxed);
+        self.next.store(0, Ordering::Release);
+    }
+
+    /// 取下一个块 [begin, end)，遍历完或者已被中止时返回 None
+    fn next(&self, len : usize) -> Option<(usize, usize)> {
+        if self.stop.load(Ordering::Acquire) {
+            return None;
+        }
+        let chunk_size = self.chunk_size.load(Ordering::Relaxed) as usize;
+        let begin = self.next.fetch_add(chunk_size, Ordering::AcqRel);
+        if begin >= len {
+            None
+        }else {
+            Some((begin, (begin + chunk_size).min(len)))
+        }
+    }
+
+    fn stop(&self) {
+        self.stop.store(true, Ordering::Release);
+    }
+}
+
+/// 按块遍历本 rank 拥有的顶点。
+/// 多个线程可以同时调用 traversal，每个块只会被一个线程处理；
+/// 任意一次 process 返回 false 后，所有线程在取下一个块时停止。调用 reset 重新开始
+pub struct VertexTraversal {
+    vertices : Vec<Vid>,
+    cursor : Cursor,
+}
+
+impl VertexTraversal {
+    pub fn new(graph : &impl Graph) -> Self {
+        let partition = graph.partition();
+        VertexTraversal {
+            vertices : (partition.start_id()..partition.end_id()).collect(),
+            cursor : Cursor::new(),
+        }
+    }
+}
+
+impl Traversal for VertexTraversal {
+    type Item = Vid;
+
+    fn reset(&self, chunk_size : u32) {
+        self.cursor.reset(chunk_size);
+    }
+
+    fn traversal(&self, process: impl Fn(&[Self::Item]) -> bool) {
+        while let Some((begin, end)) = self.cursor.next(self.vertices.len()) {
+            if !process(&self.vertices[begin..end]) {
+                self.cursor.stop();
+                return;
+            }
+        }
+    }
+}
+
+/// 按块遍历本 rank 拥有的顶点的邻接表，元素为 (顶点, 邻居)，块大小按边数计算。
+/// 两个端点都属于本 rank 的无向边会以 (u, v)、(v, u) 各出现一次。线程安全的语义同 VertexTraversal
+pub struct EdgeTraversal<'a, G> {
+    graph : &'a G,
+    start_id : usize,
+
+    /// offsets[i] 是第 i 个本地顶点的第一条边在遍历顺序中的位置
+    offsets : Vec<usize>,
+    cursor : Cursor,
+}
+
+impl<'a, G : Graph> EdgeTraversal<'a, G> {
+    pub fn new(graph : &'a G) -> Self {
+        let mut offsets = vec![0];
+        for degree in graph.degrees() {
+            offsets.push(offsets.last().unwrap() + degree as usize);
+        }
+        EdgeTraversal {
+            graph : graph,
+            start_id : graph.partition().start_id() as usize,
+            offsets : offsets,
+            cursor : Cursor::new(),
+        }
+    }
+}
+
+impl<'a, G : Graph> Traversal for EdgeTraversal<'a, G> {
+    type Item = (Vid, Vid);
+
+    fn reset(&self, chunk_size : u32) {
+        self.cursor.reset(chunk_size);
+    }
+
+    fn traversal(&self, process: impl Fn(&[Self::Item]) -> bool) {
+        let total = *self.offsets.last().unwrap();
+        let mut buffer = vec![];
+        while let Some((begin, end)) = self.cursor.next(total) {
+            buffer.clear();
+            // 块 [begin, end) 可能从某个顶点的邻接表中间开始，跨过多个顶点
+            let mut v = self.offsets.partition_point(|&offset| offset <= begin) - 1;
+            while self.offsets[v] < end {
+                let (lo, hi) = (begin.max(self.offsets[v]) - self.offsets[v], end.min(self.offsets[v + 1]) - self.offsets[v]);
+                let id = self.start_id + v;
+                self.graph.for_each_nbr_range(id, lo, hi, |to| buffer.push((id as Vid, to)));
+                v += 1;
+            }
+            if !process(&buffer) {
+                self.cursor.stop();
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+    use crate::{parallel::server::*, io::example::*, graph::{NearGraph, SeqSPartition}, common::base_structure::Edge};
+
+    fn check(edges : Vec<Edge<MyEmpty>>, communication : &impl MyMpi, vertices : Vec<Vid>, nbrs : Vec<(Vid, Vid)>) {
+        let graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges, communication);
+
+        let vertex_traversal = VertexTraversal::new(&graph);
+        let edge_traversal = EdgeTraversal::new(&graph);
+        for chunk_size in [1, 2, 3, 100] {
+            vertex_traversal.reset(chunk_size);
+            edge_traversal.reset(chunk_size);
+            let seen_vertices = Mutex::new(vec![]);
+            let seen_edges = Mutex::new(vec![]);
+            (0..4).into_par_iter().for_each(|_| {
+                vertex_traversal.traversal(|chunk| {
+                    assert!(chunk.len() <= chunk_size as usize);
+                    seen_vertices.lock().unwrap().extend_from_slice(chunk);
+                    true
+                });
+                edge_traversal.traversal(|chunk| {
+                    assert!(chunk.len() <= chunk_size as usize);
+                    seen_edges.lock().unwrap().extend_from_slice(chunk);
+                    true
+                });
+            });
+            let mut seen_vertices = seen_vertices.into_inner().unwrap();
+            let mut seen_edges = seen_edges.into_inner().unwrap();
+            seen_vertices.sort();
+            seen_edges.sort();
+            assert_eq!(seen_vertices, vertices);
+            assert_eq!(seen_edges, nbrs);
+        }
+
+        // process 返回 false 后不再继续
+        edge_traversal.reset(1);
+        let count = AtomicUsize::new(0);
+        edge_traversal.traversal(|_| count.fetch_add(1, Ordering::Relaxed) < 1);
+        assert_eq!(count.load(Ordering::Relaxed), 2.min(nbrs.len()));
+    }
+
+    #[test]
+    fn traversal0() {
+        let communicatoner = com_for_test(36, 37, 0);
+        let edges = edges(&[(0, 1), (1, 2), (2, 3), (3, 4), (1, 1), (5, 6)]);
+        let nbrs = vec![(0, 1), (1, 0), (1, 1), (1, 1), (1, 2), (2, 1), (2, 3), (3, 2), (3, 4)];
+        check(edges, &communicatoner, vec![0, 1, 2, 3], nbrs);
+    }
+
+    #[test]
+    fn traversal1() {
+        let communicatoner = com_for_test(36, 37, 1);
+        check(vec![], &communicatoner, vec![4, 5, 6], vec![(4, 3), (5, 6), (6, 5)]);
+    }
+}
@@ -0,0 +1,157 @@
+use bincode::{Encode, Decode};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::common::base_structure::{Vid, Eid, Edge};
+use crate::parallel::server::MyMpi;
+
+use super::{SeqPartition, NearGraph, NearEdge};
+
+use std::fmt::Debug;
+
+impl<EDATA, PART> NearGraph<EDATA, PART>
+where
+    PART : SeqPartition + Sync,
+    EDATA : Clone + Send + Sync + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    fn insert_nbr(&mut self, from : Vid, to : Vid, data : EDATA, out : bool) {
+        let nbr = &mut self.g[from as usize];
+        let edge = NearEdge { to : to, data : data, out : out };
+        if self.sorted {
+            let pos = nbr.partition_point(|x| x.to <= to);
+            nbr.insert(pos, edge);
+        }else {
+            nbr.push(edge);
+        }
+    }
+
+    /// 删除 from 邻接表中一条指向 to、方向为 out 的边，返回是否找到
+    fn remove_nbr(&mut self, from : Vid, to : Vid, out : bool) -> bool {
+        let nbr = &mut self.g[from as usize];
+        match nbr.iter().position(|x| x.to == to && x.out == out) {
+            Some(pos) => {
+                nbr.remove(pos);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// 批量插入和删除边，所有 rank 需要同时调用。
+    /// 插入的边通过 impl_partition（即 Pratition 的划分规则）发送到对应的 rank，出现新的顶点 id 时 vertex_num 随之增长。
+    /// 删除的边会广播给所有 rank，持有这条边的 rank 各自删除本地的副本，
+    /// 因为 HDRF 这类流式划分下边的位置无法由 edge_partition 推出。
+    /// 删除按方向匹配，每条待删除的边 (from, to) 删掉一条 from 指向 to 的边，只有反向边 to -> from 时不会删除，不存在的边被忽略。
+    /// 返回本 rank 拥有、邻接表发生变化的顶点
+    pub fn apply_updates(&mut self, inserts : Vec<Edge<EDATA>>, deletes : Vec<Edge<EDATA>>, communication : &impl MyMpi) -> Vec<Vid> {
+        let rank = communication.get_cluster_info().rank;
+
+        let max_vertex = inserts.iter().map(|x| x.from.max(x.to) + 1).max().unwrap_or_default();
+        let vertex_num = communication.reduce(max_vertex, |a, b| a.max(b));
+        if vertex_num > self.graph_info.vertex_num {
+            self.graph_info.vertex_num = vertex_num;
+            self.g.resize(vertex_num as usize, vec![]);
+            self.partition.grow(vertex_num);
+            self.properties.resize(self.partition.start_id(), self.partition.end_id());
+        }
+
+        let inserted = communication.reduce(inserts.len() as Eid, |a, b| a + b);
+        let inserts = self.partition.impl_partition(inserts, communication);
+        let deletes : Vec<Edge<EDATA>> = {
+            let msgs = vec![deletes; communication.partitions()];
+            let recv = communication.send_recv::<Vec<Edge<EDATA>>>(msgs);
+            recv.into_par_iter().flatten().collect()
+        };
+
+        let mut touched = vec![];
+        for edge in inserts {
+            self.insert_nbr(edge.from, edge.to, edge.data.clone(), true);
+            self.insert_nbr(edge.to, edge.from, edge.data, false);
+            touched.push(edge.from);
+            touched.push(edge.to);
+        }
+
+        let mut removed : Eid = 0;
+        for edge in deletes.iter() {
+            if edge.from.max(edge.to) >= self.graph_info.vertex_num {
+                continue;
+            }
+            if self.remove_nbr(edge.from, edge.to, true) {
+                self.remove_nbr(edge.to, edge.from, false);
+                touched.push(edge.from);
+                touched.push(edge.to);
+                if self.partition.counts_edge(edge.from, edge.to, rank) {
+                    removed += 1;
+                }
+            }
+        }
+        let removed = communication.reduce(removed, |a, b| a + b);
+        self.graph_info.edge_num = self.graph_info.edge_num + inserted - removed;
+
+        let start_id = self.partition.start_id();
+        let end_id = self.partition.end_id();
+        touched.retain(|&vid| vid >= start_id && vid < end_id);
+        touched.sort_unstable();
+        touched.dedup();
+        touched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::{Graph, SeqSPartition}};
+
+    #[test]
+    fn update0() {
+        let communicatoner = com_for_test(28, 29, 0);
+        let mut graph = NearGraph::<MyEmpty, SeqSPartition>::new(edges(&[(0, 1), (1, 2), (2, 3)]), &communicatoner);
+
+        let affected = graph.apply_updates(edges(&[(3, 4), (0, 5)]), edges(&[(1, 2), (0, 9)]), &communicatoner);
+        assert_eq!(affected, vec![0, 1, 2]);
+        assert_eq!(graph.graph_info.vertex_num, 6);
+        assert_eq!(graph.graph_info.edge_num, 5);
+        assert_eq!(graph.degrees(), vec![2, 1, 1]);
+    }
+
+    #[test]
+    fn update1() {
+        let communicatoner = com_for_test(28, 29, 1);
+        let mut graph = NearGraph::<MyEmpty, SeqSPartition>::new(vec![], &communicatoner);
+
+        let affected = graph.apply_updates(edges(&[(4, 5)]), vec![], &communicatoner);
+        assert_eq!(affected, vec![3, 4, 5]);
+        assert_eq!(graph.graph_info.vertex_num, 6);
+        assert_eq!(graph.graph_info.edge_num, 5);
+        assert_eq!((graph.partition().start_id(), graph.partition().end_id()), (3, 6));
+        assert_eq!(graph.degrees(), vec![2, 2, 2]);
+    }
+
+    fn check_directed(communication : &impl MyMpi, edges_of : Vec<(Vid, Vec<Vid>, Vec<Vid>)>, affected : Vec<Vid>) {
+        let list = if communication.get_cluster_info().rank == 0 { edges(&[(0, 1), (1, 2), (2, 1)]) } else { vec![] };
+        let mut graph = NearGraph::<MyEmpty, SeqSPartition>::new(list, communication);
+
+        // 只删除 2 -> 1，1 -> 2 保留；0 -> 1 没有反向边，(1, 0) 被忽略
+        let deletes = if communication.get_cluster_info().rank == 0 { edges(&[(2, 1), (1, 0)]) } else { vec![] };
+        assert_eq!(graph.apply_updates(vec![], deletes, communication), affected);
+        assert_eq!(graph.graph_info.edge_num, 2);
+        for (v, out, into) in edges_of {
+            let (mut a, mut b) = (vec![], vec![]);
+            graph.for_each_out_nbr(v as usize, |w| a.push(w));
+            graph.for_each_in_nbr(v as usize, |w| b.push(w));
+            assert_eq!((a, b), (out, into), "vertex {v}");
+        }
+    }
+
+    #[test]
+    fn update_directed0() {
+        let communicatoner = com_for_test(64, 65, 0);
+        check_directed(&communicatoner, vec![(0, vec![1], vec![]), (1, vec![2], vec![0])], vec![1]);
+    }
+
+    #[test]
+    fn update_directed1() {
+        let communicatoner = com_for_test(64, 65, 1);
+        check_directed(&communicatoner, vec![(2, vec![], vec![1])], vec![2]);
+    }
+}
@@ -0,0 +1,370 @@
+use bincode::{Encode, Decode};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::common::base_structure::{Vid, Edge};
+use crate::parallel::server::MyMpi;
+
+use super::{Pratition, SeqPartition, SeqSPartition, GraphInfo, ClusterInfo, NearGraph};
+
+use std::fmt::Debug;
+
+/// HDRF 打分中负载均衡项的权重
+const HDRF_LAMBDA : f64 = 1.0;
+const HDRF_EPSILON : f64 = 1.0;
+
+/// 按照 assign 给出的 rank 把每条边发送一次，返回本 rank 收到的边
+fn shuffle_edges<EDATA>(edges : Vec<Edge<EDATA>>, mut assign : impl FnMut(&Edge<EDATA>) -> usize, communication : &impl MyMpi) -> Vec<Edge<EDATA>>
+where
+    EDATA : Clone + Send + Debug,
+    Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+{
+    let mut msgs = vec![vec![]; communication.partitions()];
+    edges.into_iter().for_each(|edge| {
+        let p = assign(&edge);
+        msgs[p].push(edge);
+    });
+    let recv = communication.send_recv::<Vec<Edge<EDATA>>>(msgs);
+
+    recv.into_par_iter().flatten().collect()
+}
+
+/// 每个顶点在哪些 rank 上有副本，每个顶点占 ceil(partitions / 64) 个 u64
+struct ReplicaSet {
+    words : usize,
+    bits : Vec<u64>,
+}
+
+impl ReplicaSet {
+    fn new(vertex_num : usize, partitions : usize) -> Self {
+        let words = (partitions + 63) / 64;
+        ReplicaSet { words : words, bits : vec![0; vertex_num * words] }
+    }
+
+    fn contains(&self, v : usize, p : usize) -> bool {
+        self.bits[v * self.words + p / 64] >> (p % 64) & 1 == 1
+    }
+
+    fn insert(&mut self, v : usize, p : usize) {
+        self.bits[v * self.words + p / 64] |= 1 << (p % 64);
+    }
+}
+
+/// 2D 网格划分。rank 排成 rows * cols 的网格，边 (u, v) 放在 (row(u), col(v)) 上，
+/// 每个顶点最多有 rows + cols - 1 个副本。顶点的 master 仍然按 SeqSPartition 连续划分。
+#[derive(Debug, Encode, Decode)]
+pub struct GridPartition {
+    master : SeqSPartition,
+    rows : usize,
+    cols : usize,
+}
+
+impl GridPartition {
+    /// 选取最接近正方形的网格：rows 为不超过 sqrt(partitions) 的最大因子
+    fn shape(partitions : usize) -> (usize, usize) {
+        let mut rows = (partitions as f64).sqrt() as usize;
+        while rows > 1 && partitions % rows != 0 {
+            rows -= 1;
+        }
+        let rows = rows.max(1);
+        (rows, partitions / rows)
+    }
+}
+
+impl Pratition for GridPartition {
+    fn vertex_partition(&self, vid : &Vid) -> usize {
+        self.master.vertex_partition(vid)
+    }
+
+    fn edge_partition<EDATA>(&self, edge : &Edge<EDATA>) -> usize {
+        // 图按无向存储，(u, v) 与 (v, u) 必须落在同一个 rank
+        let pu = self.vertex_partition(&edge.from.min(edge.to));
+        let pv = self.vertex_partition(&edge.from.max(edge.to));
+        (pu / self.cols) * self.cols + pv % self.cols
+    }
+}
+
+impl SeqPartition for GridPartition {
+    fn new(degrees : Vec<Vid>, graph_info : &GraphInfo, cluster_info : &ClusterInfo) -> Self {
+        let (rows, cols) = GridPartition::shape(cluster_info.partitions);
+        GridPartition {
+            master : SeqSPartition::new(degrees, graph_info, cluster_info),
+            rows : rows,
+            cols : cols,
+        }
+    }
+
+    fn impl_partition<EDATA>(&self, edges : Vec<Edge<EDATA>>, communication : &impl MyMpi) -> Vec<Edge<EDATA>>
+    where
+        EDATA : Clone + Send + Debug,
+        Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+    {
+        println!("impl grid partition {} x {}", self.rows, self.cols);
+        shuffle_edges(edges, |edge| self.edge_partition(edge), communication)
+    }
+
+    fn start_id(&self) -> Vid {
+        self.master.start_id()
+    }
+
+    fn end_id(&self) -> Vid {
+        self.master.end_id()
+    }
+
+    fn vertex_cut(&self) -> bool {
+        true
+    }
+
+    fn grow(&mut self, vertex_num : Vid) {
+        self.master.grow(vertex_num);
+    }
+}
+
+/// HDRF（High-Degree Replicated First）流式划分。
+/// 每个 rank 独立地流式处理自己读到的边，用局部的度数和副本信息贪心选择目标 rank，
+/// 优先复制高度数顶点。顶点的 master 仍然按 SeqSPartition 连续划分。
+#[derive(Debug, Encode, Decode)]
+pub struct HdrfPartition {
+    master : SeqSPartition,
+    vertex_num : Vid,
+    partitions : usize,
+}
+
+impl Pratition for HdrfPartition {
+    fn vertex_partition(&self, vid : &Vid) -> usize {
+        self.master.vertex_partition(vid)
+    }
+
+    /// HDRF 的分配依赖流式状态，这里只给出无状态的兜底：放到较小端点的 master 上
+    fn edge_partition<EDATA>(&self, edge : &Edge<EDATA>) -> usize {
+        self.vertex_partition(&edge.from.min(edge.to))
+    }
+}
+
+impl SeqPartition for HdrfPartition {
+    fn new(degrees : Vec<Vid>, graph_info : &GraphInfo, cluster_info : &ClusterInfo) -> Self {
+        HdrfPartition {
+            master : SeqSPartition::new(degrees, graph_info, cluster_info),
+            vertex_num : graph_info.vertex_num,
+            partitions : cluster_info.partitions,
+        }
+    }
+
+    fn impl_partition<EDATA>(&self, edges : Vec<Edge<EDATA>>, communication : &impl MyMpi) -> Vec<Edge<EDATA>>
+    where
+        EDATA : Clone + Send + Debug,
+        Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+    {
+        println!("impl hdrf partition");
+        let partitions = self.partitions;
+        let mut partial_degree : Vec<Vid> = vec![0; self.vertex_num as usize];
+        let mut replicas = ReplicaSet::new(self.vertex_num as usize, partitions);
+        let mut loads : Vec<usize> = vec![0; partitions];
+
+        shuffle_edges(edges, |edge| {
+            let (u, v) = (edge.from as usize, edge.to as usize);
+            partial_degree[u] += 1;
+            partial_degree[v] += 1;
+            let du = partial_degree[u] as f64;
+            let dv = partial_degree[v] as f64;
+            let theta_u = du / (du + dv);
+            let theta_v = 1.0 - theta_u;
+
+            let max_load = *loads.iter().max().unwrap() as f64;
+            let min_load = *loads.iter().min().unwrap() as f64;
+
+            let best = (0..partitions).map(|p| {
+                let mut rep = 0.0;
+                if replicas.contains(u, p) {
+                    rep += 2.0 - theta_u;
+                }
+                if replicas.contains(v, p) {
+                    rep += 2.0 - theta_v;
+                }
+                let bal = HDRF_LAMBDA * (max_load - loads[p] as f64) / (HDRF_EPSILON + max_load - min_load);
+                (p, rep + bal)
+            })
+            .fold((0, f64::MIN), |a, b| if b.1 > a.1 { b } else { a })
+            .0;
+
+            replicas.insert(u, best);
+            replicas.insert(v, best);
+            loads[best] += 1;
+            best
+        }, communication)
+    }
+
+    fn start_id(&self) -> Vid {
+        self.master.start_id()
+    }
+
+    fn end_id(&self) -> Vid {
+        self.master.end_id()
+    }
+
+    fn vertex_cut(&self) -> bool {
+        true
+    }
+
+    fn grow(&mut self, vertex_num : Vid) {
+        self.master.grow(vertex_num);
+        self.vertex_num = self.vertex_num.max(vertex_num);
+    }
+}
+
+/// vertex-cut 下的 master/mirror 信息。
+/// 本地邻接表里出现但不属于本 rank 的顶点是 mirror，它在 master 所在的 rank 上有完整的状态。
+#[derive(Debug)]
+pub struct MirrorInfo {
+    /// mirrors[p] 为本地出现、master 在 rank p 上的顶点，升序
+    pub mirrors : Vec<Vec<Vid>>,
+
+    /// mirrored_by[p] 为本 rank 拥有、并且在 rank p 上有 mirror 的顶点，升序
+    pub mirrored_by : Vec<Vec<Vid>>,
+}
+
+impl MirrorInfo {
+    pub fn new<EDATA, PART>(graph : &NearGraph<EDATA, PART>, communication : &impl MyMpi) -> Self
+    where
+        PART : SeqPartition + Sync,
+        EDATA : Clone + Send + Sync,
+        Vec<Edge<EDATA>> : IntoParallelIterator<Item = Edge<EDATA>> + Encode + Decode,
+    {
+        let rank = communication.get_cluster_info().rank;
+        let mut mirrors = vec![vec![]; communication.partitions()];
+        graph.g.iter().enumerate().for_each(|(id, nbr)| {
+            let master = graph.partition.vertex_partition(&(id as Vid));
+            if !nbr.is_empty() && master != rank {
+                mirrors[master].push(id as Vid);
+            }
+        });
+
+        let mirrored_by = communication.send_recv::<Vec<Vid>>(mirrors.clone());
+
+        MirrorInfo {
+            mirrors : mirrors,
+            mirrored_by : mirrored_by,
+        }
+    }
+
+    /// 把 master 上的值同步到各个 mirror。value 以 master 顶点的全局 id 取值，
+    /// 返回本 rank 上每个 mirror 收到的 (vid, value)
+    pub fn sync_to_mirrors<T>(&self, value : impl Fn(Vid) -> T, communication : &impl MyMpi) -> Vec<(Vid, T)>
+    where
+        T : Encode + Decode + Send + 'static,
+    {
+        let msgs : Vec<Vec<T>> = self.mirrored_by.iter().map(|vids| {
+            vids.iter().map(|&vid| value(vid)).collect()
+        }).collect();
+        let recv = communication.send_recv::<Vec<T>>(msgs);
+
+        recv.into_iter().zip(self.mirrors.iter()).flat_map(|(values, vids)| {
+            vids.iter().cloned().zip(values.into_iter()).collect::<Vec<_>>()
+        }).collect()
+    }
+
+    /// 把 mirror 上的局部值汇总回 master。value 以 mirror 顶点的全局 id 取值，
+    /// 返回本 rank 拥有的顶点收到的所有 (vid, value)，由调用方合并
+    pub fn gather_from_mirrors<T>(&self, value : impl Fn(Vid) -> T, communication : &impl MyMpi) -> Vec<(Vid, T)>
+    where
+        T : Encode + Decode + Send + 'static,
+    {
+        let msgs : Vec<Vec<T>> = self.mirrors.iter().map(|vids| {
+            vids.iter().map(|&vid| value(vid)).collect()
+        }).collect();
+        let recv = communication.send_recv::<Vec<T>>(msgs);
+
+        recv.into_iter().zip(self.mirrored_by.iter()).flat_map(|(values, vids)| {
+            vids.iter().cloned().zip(values.into_iter()).collect::<Vec<_>>()
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parallel::server::*, io::example::*, graph::Graph};
+
+    /// 把 mirror 上的局部度数汇总到 master，得到每个 owned 顶点的全局度数
+    fn global_degrees<PART>(graph : &NearGraph<MyEmpty, PART>, communication : &impl MyMpi) -> Vec<Vid>
+    where
+        PART : SeqPartition + Sync,
+    {
+        let mirror_info = MirrorInfo::new(graph, communication);
+        let start_id = graph.partition().start_id();
+        let mut degrees = graph.degrees();
+        mirror_info.gather_from_mirrors(|vid| graph.nbr(vid as usize).len() as Vid, communication)
+            .into_iter()
+            .for_each(|(vid, degree)| degrees[(vid - start_id) as usize] += degree);
+        degrees
+    }
+
+    #[test]
+    fn grid_replicas() {
+        let graph_info = GraphInfo { vertex_num : 100, edge_num : 0 };
+        let grid = GridPartition::new(vec![], &graph_info, &ClusterInfo { partitions : 6, rank : 0 });
+        assert_eq!((grid.rows, grid.cols), (2, 3));
+
+        for u in 0..100 {
+            let mut parts = vec![false; 6];
+            for v in 0..100 {
+                parts[grid.edge_partition(&Edge { from : u, to : v, data : () })] = true;
+                parts[grid.edge_partition(&Edge { from : v, to : u, data : () })] = true;
+            }
+            assert!(parts.iter().filter(|&&x| x).count() <= grid.rows + grid.cols - 1);
+        }
+    }
+
+    #[test]
+    fn replica_set() {
+        let mut replicas = ReplicaSet::new(3, 130);
+        for p in [0, 63, 64, 129] {
+            replicas.insert(1, p);
+        }
+        assert!((0..130).all(|p| !replicas.contains(0, p) && !replicas.contains(2, p)));
+        assert_eq!((0..130).filter(|&p| replicas.contains(1, p)).collect::<Vec<_>>(), vec![0, 63, 64, 129]);
+    }
+
+    #[test]
+    fn grid_mirror0() {
+        let communicatoner = com_for_test(10, 11, 0);
+        let graph = NearGraph::<MyEmpty, GridPartition>::new(sample_edges(), &communicatoner);
+
+        let local_edges = graph.g.iter().map(|x| x.len()).sum::<usize>() / 2;
+        let edge_num = communicatoner.reduce(local_edges, |a, b| a + b);
+        assert_eq!(edge_num, 7);
+        assert_eq!(global_degrees(&graph, &communicatoner), vec![3, 3, 3, 2]);
+    }
+
+    #[test]
+    fn grid_mirror1() {
+        let communicatoner = com_for_test(10, 11, 1);
+        let graph = NearGraph::<MyEmpty, GridPartition>::new(vec![], &communicatoner);
+
+        let local_edges = graph.g.iter().map(|x| x.len()).sum::<usize>() / 2;
+        let edge_num = communicatoner.reduce(local_edges, |a, b| a + b);
+        assert_eq!(edge_num, 7);
+        assert_eq!(global_degrees(&graph, &communicatoner), vec![1, 2]);
+    }
+
+    #[test]
+    fn hdrf_mirror0() {
+        let communicatoner = com_for_test(12, 13, 0);
+        let graph = NearGraph::<MyEmpty, HdrfPartition>::new(sample_edges(), &communicatoner);
+
+        let mirror_info = MirrorInfo::new(&graph, &communicatoner);
+        let synced = mirror_info.sync_to_mirrors(|vid| vid * 10, &communicatoner);
+        assert!(synced.iter().all(|&(vid, value)| value == vid * 10 && vid >= 4));
+        assert_eq!(global_degrees(&graph, &communicatoner), vec![3, 3, 3, 2]);
+    }
+
+    #[test]
+    fn hdrf_mirror1() {
+        let communicatoner = com_for_test(12, 13, 1);
+        let graph = NearGraph::<MyEmpty, HdrfPartition>::new(vec![], &communicatoner);
+
+        let mirror_info = MirrorInfo::new(&graph, &communicatoner);
+        let synced = mirror_info.sync_to_mirrors(|vid| vid * 10, &communicatoner);
+        assert!(synced.iter().all(|&(vid, value)| value == vid * 10 && vid < 4));
+        assert_eq!(global_degrees(&graph, &communicatoner), vec![1, 2]);
+    }
+}
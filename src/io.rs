@@ -6,6 +6,7 @@ use flate2::read::GzDecoder;
 pub mod data;
 pub mod csv;
 pub mod example;
+pub mod generator;
 use data::*;
 
 pub trait FromArrow {
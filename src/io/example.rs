@@ -1,6 +1,10 @@
 use arrow::array::{ArrayRef, Int32Array, StringArray, Float32Array};
 use bincode::{Encode, Decode};
 
+use crate::{traits::Weight, impl_weight, graph::mmap::FixedWidth};
+#[cfg(test)]
+use crate::common::base_structure::{Vid, Edge};
+
 use super::FromArrow;
 
 /// 实现FromArrow的EDATA类型
@@ -31,9 +35,26 @@ impl FromArrow for MyEDATA {
     }
 }
 
+impl_weight!(MyEDATA, f32_data);
+
 #[derive(Debug, PartialEq, Clone, Encode, Decode)]
 pub struct MyEmpty {}
 
+/// 无权图的每条边权重为 1
+impl Weight for MyEmpty {
+    fn weight(&self) -> f32 {
+        1.0
+    }
+}
+
+impl FixedWidth for MyEmpty {
+    const WIDTH : usize = 0;
+    fn write_to(&self, _buf : &mut [u8]) {}
+    fn read_from(_buf : &[u8]) -> Self {
+        MyEmpty {}
+    }
+}
+
 impl FromArrow for MyEmpty {
     fn from(arrow_data : Vec<ArrayRef>, len : usize) -> Vec<Self>
         where Self: Sized 
@@ -42,6 +63,24 @@ impl FromArrow for MyEmpty {
     }
 }
 
+/// 测试用：把 (from, to) 列表转成无权边
+#[cfg(test)]
+pub(crate) fn edges(list : &[(Vid, Vid)]) -> Vec<Edge<MyEmpty>> {
+    list.iter().map(|&(from, to)| Edge { from : from, to : to, data : MyEmpty {} }).collect()
+}
+
+/// 测试用：把 (from, to, 边权) 列表转成带权边
+#[cfg(test)]
+pub(crate) fn weighted_edges(list : &[(Vid, Vid, f32)]) -> Vec<Edge<f32>> {
+    list.iter().map(|&(from, to, data)| Edge { from : from, to : to, data : data }).collect()
+}
+
+/// 测试用的小图：0 1 2 3 成环，0 - 2 是弦，4 - 5 经过 1 - 5 连到环上
+#[cfg(test)]
+pub(crate) fn sample_edges() -> Vec<Edge<MyEmpty>> {
+    edges(&[(0, 1), (1, 2), (2, 3), (3, 0), (0, 2), (4, 5), (1, 5)])
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{io::{csv::CsvReader, ReadOption, FileRead}, common::base_structure::edge::Edge};
@@ -0,0 +1,295 @@
+use std::ops::Range;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::common::base_structure::{Vid, Eid, Edge};
+use crate::graph::ClusterInfo;
+
+/// splitmix64 的混合函数
+fn mix(mut x : u64) -> u64 {
+    x = x.wrapping_add(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^ (x >> 31)
+}
+
+/// splitmix64 随机数生成器。每条边使用由 (seed, 边的下标) 决定的独立随机数流，
+/// 所以生成结果与 rank 的个数无关
+pub struct Rng {
+    state : u64,
+}
+
+impl Rng {
+    pub fn new(seed : u64) -> Self {
+        Rng { state : seed }
+    }
+
+    fn for_index(seed : u64, index : u64) -> Self {
+        Rng::new(mix(seed ^ mix(index)))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        mix(self.state)
+    }
+
+    /// [0, n) 上的均匀整数
+    pub fn below(&mut self, n : u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// [0, 1) 上的均匀浮点数
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+}
+
+/// R-MAT 递归划分邻接矩阵时落入四个象限的概率，d = 1 - a - b - c
+#[derive(Debug, Clone, Copy)]
+pub struct RmatParams {
+    pub a : f64,
+    pub b : f64,
+    pub c : f64,
+}
+
+/// Graph500 使用的 R-MAT 参数
+pub const GRAPH500 : RmatParams = RmatParams { a : 0.57, b : 0.19, c : 0.19 };
+
+/// 分布式的合成图生成器。每个 rank 只生成属于自己的那一段边，
+/// 拼起来的边集只由 seed 决定。data 为每条边生成 EDATA，参数是边的两个端点和这条边的随机数流
+pub struct Generator {
+    pub seed : u64,
+    rank : usize,
+    partitions : usize,
+}
+
+impl Generator {
+    pub fn new(seed : u64, cluster_info : &ClusterInfo) -> Self {
+        Generator {
+            seed : seed,
+            rank : cluster_info.rank,
+            partitions : cluster_info.partitions,
+        }
+    }
+
+    /// 本 rank 负责生成的边的下标范围
+    fn local_range(&self, m : Eid) -> Range<Eid> {
+        let chunk = m / self.partitions as Eid;
+        let rest = m % self.partitions as Eid;
+        let rank = self.rank as Eid;
+        let begin = rank * chunk + rank.min(rest);
+        let end = begin + chunk + (rank < rest) as Eid;
+        begin..end
+    }
+
+    /// 按下标生成本 rank 的边，edge 返回第 index 条边的两个端点
+    fn generate<EDATA>(
+        &self,
+        m : Eid,
+        edge : impl Fn(Eid, &mut Rng) -> (Vid, Vid) + Sync,
+        data : impl Fn(Vid, Vid, &mut Rng) -> EDATA + Sync,
+    ) -> Vec<Edge<EDATA>>
+    where
+        EDATA : Send,
+    {
+        let seed = self.seed;
+        self.local_range(m).into_par_iter().map(|index| {
+            let mut rng = Rng::for_index(seed, index);
+            let (from, to) = edge(index, &mut rng);
+            Edge { from : from, to : to, data : data(from, to, &mut rng) }
+        }).collect()
+    }
+
+    /// Erdős–Rényi G(n, m)：m 条边，两个端点在 n 个顶点中均匀选取，不含自环，可能有平行边
+    pub fn erdos_renyi<EDATA : Send>(&self, n : Vid, m : Eid, data : impl Fn(Vid, Vid, &mut Rng) -> EDATA + Sync) -> Vec<Edge<EDATA>> {
+        assert!(n >= 2, "erdos_renyi needs at least 2 vertices");
+        self.generate(m, |_, rng| {
+            let from = rng.below(n as u64) as Vid;
+            let to = ((from as u64 + 1 + rng.below(n as u64 - 1)) % n as u64) as Vid;
+            (from, to)
+        }, data)
+    }
+
+    /// R-MAT / Kronecker 图：2^scale 个顶点，edge_factor * 2^scale 条边。
+    /// 和 Graph500 一样对顶点 id 做一次置换打散高度数顶点，保留自环和平行边
+    pub fn rmat<EDATA : Send>(&self, scale : u32, edge_factor : u32, params : RmatParams, data : impl Fn(Vid, Vid, &mut Rng) -> EDATA + Sync) -> Vec<Edge<EDATA>> {
+        assert!(scale > 0 && scale <= 31, "rmat scale must be in 1..=31");
+        let n = 1u64 << scale;
+        let mask = n - 1;
+        let (ab, abc) = (params.a + params.b, params.a + params.b + params.c);
+        let (mul1, mul2) = (mix(self.seed) | 1, mix(self.seed ^ 1) | 1);
+        // 乘奇数和右移异或在 2^scale 上都是双射
+        let permute = |v : u64| {
+            let v = v.wrapping_mul(mul1) & mask;
+            let v = v ^ (v >> (scale / 2).max(1));
+            (v.wrapping_mul(mul2) & mask) as Vid
+        };
+        self.generate(edge_factor as Eid * n, |_, rng| {
+            let (mut from, mut to) = (0u64, 0u64);
+            for _ in 0..scale {
+                let p = rng.next_f64();
+                let (row, col) = if p < params.a { (0, 0) } else if p < ab { (0, 1) } else if p < abc { (1, 0) } else { (1, 1) };
+                from = (from << 1) | row;
+                to = (to << 1) | col;
+            }
+            (permute(from), permute(to))
+        }, data)
+    }
+
+    /// Barabási–Albert 优先连接：n 个顶点，每个顶点连出 d 条边。
+    /// 采用 Sanders–Schulz 的按边独立生成方法：把 Batagelj–Brandes 算法里的边端点数组 M 的每个位置
+    /// 用哈希随机数回溯求值，不需要全局状态。第一个顶点的边是自环
+    pub fn barabasi_albert<EDATA : Send>(&self, n : Vid, d : u32, data : impl Fn(Vid, Vid, &mut Rng) -> EDATA + Sync) -> Vec<Edge<EDATA>> {
+        let d = d as Eid;
+        let seed = self.seed;
+        // M[2i] = i / d，M[2i + 1] = M[r]，r 在 [0, 2i] 中均匀选取
+        let target = |mut pos : Eid| {
+            loop {
+                let r = Rng::for_index(seed ^ 0x5BD1E995, pos).below(pos);
+                if r % 2 == 0 {
+                    return (r / 2 / d) as Vid;
+                }
+                pos = r;
+            }
+        };
+        self.generate(n as Eid * d, |index, _| ((index / d) as Vid, target(2 * index + 1)), data)
+    }
+
+    /// rows × cols 的二维网格，顶点 r * cols + c 与右边、下边的顶点相连
+    pub fn grid<EDATA : Send>(&self, rows : Vid, cols : Vid, data : impl Fn(Vid, Vid, &mut Rng) -> EDATA + Sync) -> Vec<Edge<EDATA>> {
+        let (rows_e, cols_e) = (rows as Eid, cols as Eid);
+        let horizontal = rows_e * cols_e.saturating_sub(1);
+        let vertical = rows_e.saturating_sub(1) * cols_e;
+        self.generate(horizontal + vertical, |index, _| {
+            if index < horizontal {
+                let (r, c) = (index / (cols_e - 1), index % (cols_e - 1));
+                let from = (r * cols_e + c) as Vid;
+                (from, from + 1)
+            }else {
+                let from = (index - horizontal) as Vid;
+                (from, from + cols)
+            }
+        }, data)
+    }
+
+    /// 星形图，顶点 0 与其余 n - 1 个顶点相连
+    pub fn star<EDATA : Send>(&self, n : Vid, data : impl Fn(Vid, Vid, &mut Rng) -> EDATA + Sync) -> Vec<Edge<EDATA>> {
+        self.generate((n as Eid).saturating_sub(1), |index, _| (0, index as Vid + 1), data)
+    }
+
+    /// 链 0 - 1 - ... - (n - 1)
+    pub fn chain<EDATA : Send>(&self, n : Vid, data : impl Fn(Vid, Vid, &mut Rng) -> EDATA + Sync) -> Vec<Edge<EDATA>> {
+        self.generate((n as Eid).saturating_sub(1), |index, _| (index as Vid, index as Vid + 1), data)
+    }
+
+    /// n 个顶点的完全图，每对顶点 (u, v)，u < v 之间一条边
+    pub fn complete<EDATA : Send>(&self, n : Vid, data : impl Fn(Vid, Vid, &mut Rng) -> EDATA + Sync) -> Vec<Edge<EDATA>> {
+        let n = n as Eid;
+        // 第 u 行之前的边数
+        let before = |u : Eid| u * (2 * n - u - 1) / 2;
+        self.generate(n * n.saturating_sub(1) / 2, |index, _| {
+            let (mut lo, mut hi) = (0, n - 1);
+            while lo + 1 < hi {
+                let mid = (lo + hi) / 2;
+                if before(mid) <= index { lo = mid } else { hi = mid }
+            }
+            let from = lo;
+            let to = from + 1 + (index - before(from));
+            (from as Vid, to as Vid)
+        }, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::io::example::MyEmpty;
+
+    fn cluster(rank : usize, partitions : usize) -> ClusterInfo {
+        ClusterInfo { partitions : partitions, rank : rank }
+    }
+
+    /// 把所有 rank 生成的边拼起来
+    fn all_edges(partitions : usize, f : impl Fn(&Generator) -> Vec<Edge<MyEmpty>>) -> Vec<(Vid, Vid)> {
+        (0..partitions).flat_map(|rank| {
+            f(&Generator::new(42, &cluster(rank, partitions))).into_iter().map(|edge| (edge.from, edge.to))
+        }).collect()
+    }
+
+    fn empty(_ : Vid, _ : Vid, _ : &mut Rng) -> MyEmpty {
+        MyEmpty {}
+    }
+
+    #[test]
+    fn independent_of_partitions() {
+        let er = |g : &Generator| g.erdos_renyi(100, 1000, empty);
+        let rmat = |g : &Generator| g.rmat(8, 16, GRAPH500, empty);
+        let ba = |g : &Generator| g.barabasi_albert(200, 3, empty);
+        assert_eq!(all_edges(1, er), all_edges(3, er));
+        assert_eq!(all_edges(1, rmat), all_edges(4, rmat));
+        assert_eq!(all_edges(1, ba), all_edges(5, ba));
+
+        let edges = all_edges(2, er);
+        assert_eq!(edges.len(), 1000);
+        assert!(edges.iter().all(|&(u, v)| u != v && u < 100 && v < 100));
+    }
+
+    #[test]
+    fn rmat_skewed() {
+        let edges = all_edges(2, |g| g.rmat(10, 16, GRAPH500, empty));
+        assert_eq!(edges.len(), 16 << 10);
+        let mut degree = vec![0usize; 1 << 10];
+        edges.iter().for_each(|&(u, v)| {
+            degree[u as usize] += 1;
+            degree[v as usize] += 1;
+        });
+        let max = *degree.iter().max().unwrap();
+        assert!(max > 20 * 32, "max degree {max} is not skewed");
+    }
+
+    #[test]
+    fn barabasi_albert_prefers_old_vertices() {
+        let edges = all_edges(3, |g| g.barabasi_albert(1000, 2, empty));
+        assert_eq!(edges.len(), 2000);
+        assert!(edges.iter().all(|&(u, v)| v <= u));
+        let mut degree = vec![0usize; 1000];
+        edges.iter().for_each(|&(u, v)| {
+            degree[u as usize] += 1;
+            degree[v as usize] += 1;
+        });
+        let early : usize = degree[..10].iter().sum();
+        let late : usize = degree[990..].iter().sum();
+        assert!(early > 3 * late, "early {early} late {late}");
+    }
+
+    #[test]
+    fn regular_graphs() {
+        let grid = all_edges(3, |g| g.grid(3, 4, empty));
+        assert_eq!(grid.len(), 3 * 3 + 2 * 4);
+        assert!(grid.contains(&(0, 1)) && grid.contains(&(7, 11)) && !grid.contains(&(3, 4)));
+
+        assert_eq!(all_edges(2, |g| g.star(4, empty)), vec![(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(all_edges(2, |g| g.chain(4, empty)), vec![(0, 1), (1, 2), (2, 3)]);
+
+        let complete = all_edges(4, |g| g.complete(6, empty));
+        let set : HashSet<(Vid, Vid)> = complete.iter().cloned().collect();
+        assert_eq!(complete.len(), 15);
+        assert_eq!(set.len(), 15);
+        assert!(complete.iter().all(|&(u, v)| u < v && v < 6));
+    }
+
+    #[test]
+    fn edge_data_reproducible() {
+        let weights = |rank| {
+            Generator::new(7, &cluster(rank, 1)).chain(5, |_, _, rng| rng.next_f32())
+        };
+        let a = weights(0);
+        assert_eq!(a, weights(0));
+        assert!(a.iter().all(|edge| edge.data >= 0.0 && edge.data < 1.0));
+    }
+}
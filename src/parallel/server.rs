@@ -78,6 +78,12 @@ pub trait MyMpi {
     where
         DATA : Encode + Decode + Send + Clone
     ;
+
+    // 将所有rank的data收集到rank root, root上返回值中第i个值为rank i的data, 其余rank返回空
+    fn gather<DATA>(&self, data : DATA, root : usize) -> Vec<DATA>
+    where
+        DATA : Encode + Decode + Send + 'static
+    ;
 }
 
 #[derive(Debug)]
@@ -226,6 +232,19 @@ impl MyMpi for SyncCommunicationer {
         recv.into_iter().reduce(f).unwrap()
     }
 
+    fn gather<DATA>(&self, data : DATA, root : usize) -> Vec<DATA>
+    where
+        DATA : Encode + Decode + Send + 'static
+    {
+        let partitions = self.endpoints.len();
+        let mut msgs : Vec<Vec<DATA>> = (0..partitions).map(|_| vec![]).collect();
+        msgs[root].push(data);
+
+        let recv = self.send_recv(msgs);
+
+        recv.into_iter().flatten().collect()
+    }
+
     fn get_cluster_info(&self) -> &ClusterInfo {
         &self.cluster_info
     }
@@ -237,10 +256,10 @@ impl MyMpi for SyncCommunicationer {
 
 pub fn com_for_test(port1 : i32, port2 : i32, rank : usize) -> SyncCommunicationer{
     SyncCommunicationer {
-        addr: format!("[::1]:1000{}", if rank == 0 {port1} else {port2}).parse().unwrap(),
+        addr: format!("[::1]:{}", 10000 + if rank == 0 {port1} else {port2}).parse().unwrap(),
         cluster_info: ClusterInfo { partitions : 2, rank : rank},
-        endpoints: vec![Endpoint::from_shared(format!("http://[::1]:1000{port1}").to_string()).unwrap(), 
-        Endpoint::from_shared(format!("http://[::1]:1000{port2}").to_string()).unwrap()],
+        endpoints: vec![Endpoint::from_shared(format!("http://[::1]:{}", 10000 + port1)).unwrap(), 
+        Endpoint::from_shared(format!("http://[::1]:{}", 10000 + port2)).unwrap()],
     }
 }
 
@@ -3,4 +3,35 @@ pub trait Traversal {
     fn reset(&self, chunk_size : u32);
     // return true 表示还可以继续遍历，线程安全
     fn traversal(&self, process: impl Fn(&[Self::Item]) -> bool);
-}
\ No newline at end of file
+}
+
+/// 可以当作边权的 EDATA
+pub trait Weight {
+    fn weight(&self) -> f32;
+}
+
+macro_rules! impl_weight_numeric {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn weight(&self) -> f32 {
+                    *self as f32
+                }
+            }
+        )*
+    };
+}
+
+impl_weight_numeric!(i32, u32, i64, u64, f32, f64);
+
+/// 用结构体的某个数值字段作为边权，例如 `impl_weight!(MyEDATA, f32_data);`
+#[macro_export]
+macro_rules! impl_weight {
+    ($t:ty, $field:ident) => {
+        impl $crate::traits::Weight for $t {
+            fn weight(&self) -> f32 {
+                self.$field as f32
+            }
+        }
+    };
+}